@@ -1,15 +1,31 @@
-use crate::converter::batch_processor::BatchProcessor;
-use crate::converter::image_to_pdf::{ImageToPdfConverter, PdfConfig, InputType, PageOrientation, PageMode};
+use crate::converter::batch_processor::{BatchOptions, BatchProcessor};
+use crate::converter::cancel::CancellationToken;
+use crate::converter::discovery::DiscoverySettings;
+use crate::converter::error::ConvertError;
+use crate::converter::image_to_pdf::ImageToPdfConverter;
+use crate::converter::metadata::MetadataPolicy;
+use crate::converter::naming::{NamingSettings, TEMPLATE_TOKENS};
+use crate::converter::output_guard::{CollisionPolicy, OutputSettings};
+use crate::converter::pdf_converter::{PageBackground, PdfOutput, PdfRenderSettings, RenderSize};
+use crate::converter::pdf_to_image::PdfToImageConverter;
+use crate::converter::pdf_text::{PdfTextSettings, TextOutput};
+use crate::converter::png_quantizer::PngQuantOptions;
+use crate::converter::progress::{describe_task, ProgressEvent, ProgressSink};
+use crate::converter::pure_watermark::PureWatermarkProcessor;
+use crate::converter::resize::{FitMode, ResizeMode, ResizeSettings};
 use crate::converter::simple_watermark::WatermarkPosition;
+use crate::converter::turbo_encoder::{ChromaSubsampling, JpegBackend, JpegEncoderSettings};
 use crate::ui::{components, styles, menu_bar};
-use crate::utils::config::{AppConfig, OutputFormat, ProcessingMode, AppMode, PdfPageOrientation};
+use crate::utils::config::{AdvancedSettings, AppConfig, FolderSettings, OutputFormat, ProcessingMode, AppMode, PdfPageOrientation};
 use crate::utils::file_utils;
 use eframe::egui;
-use image::DynamicImage;
 use rfd::FileDialog;
 use std::path::Path;
 use tokio::sync::mpsc;
 
+/// 找不到 PDFium 动态库时附在错误后面的安装说明
+const PDFIUM_INSTALL_HELP: &str = "\n\n📋 解决方案：\n1. 下载pdfium.dll文件到程序目录\n2. 下载地址: https://github.com/bblanchon/pdfium-binaries/releases\n3. 选择Windows x64版本\n4. 解压后将pdfium.dll复制到exe同目录\n\n或者重新编译程序使用静态链接";
+
 #[derive(Debug, Clone, Default)]
 pub struct ProgressUpdate {
     pub processed: usize,
@@ -18,6 +34,46 @@ pub struct ProgressUpdate {
     pub current_file: String,
     pub is_complete: bool,
     pub error_message: Option<String>,
+    /// 任务被取消，`processed`/`failed` 为取消前的部分结果
+    pub cancelled: bool,
+    /// 因缺少密码或密码错误而无法打开的PDF，界面据此弹出密码输入框
+    pub password_needed: Option<std::path::PathBuf>,
+}
+
+/// 把库层的进度事件转换为界面使用的 `ProgressUpdate`
+impl ProgressSink for mpsc::UnboundedSender<ProgressUpdate> {
+    fn on_event(&self, event: ProgressEvent) {
+        let update = match event {
+            ProgressEvent::Started { total } => ProgressUpdate {
+                total,
+                current_file: format!("开始处理，共 {} 个任务", total),
+                ..Default::default()
+            },
+            ProgressEvent::FileDone { path, page, processed, failed, total } => ProgressUpdate {
+                processed,
+                failed,
+                total,
+                current_file: describe_task(&path, page),
+                ..Default::default()
+            },
+            ProgressEvent::FileFailed { path, page, error, processed, failed, total } => ProgressUpdate {
+                processed,
+                failed,
+                total,
+                current_file: format!("{} 失败: {}", describe_task(&path, page), error),
+                ..Default::default()
+            },
+            ProgressEvent::Finished { processed, failed, total, cancelled } => ProgressUpdate {
+                processed,
+                failed,
+                total,
+                is_complete: true,
+                cancelled,
+                ..Default::default()
+            },
+        };
+        let _ = self.send(update);
+    }
 }
 
 pub struct ImageConverterApp {
@@ -31,11 +87,23 @@ pub struct ImageConverterApp {
     tokio_runtime: tokio::runtime::Runtime,
     progress_receiver: mpsc::UnboundedReceiver<ProgressUpdate>,
     progress_sender: mpsc::UnboundedSender<ProgressUpdate>,
+    /// 当前任务的取消令牌，每次开始处理时重新创建
+    cancel_token: CancellationToken,
     menu_bar_state: menu_bar::MenuBarState,
+    /// 加密PDF的密码输入框，输入后重新开始任务
+    password_prompt: Option<PasswordPrompt>,
     #[allow(dead_code)]
     last_button_click: std::time::Instant,
 }
 
+/// 密码输入框状态
+struct PasswordPrompt {
+    path: std::path::PathBuf,
+    password: String,
+    /// 已经提供过密码但不正确
+    incorrect: bool,
+}
+
 impl ImageConverterApp {
     pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
         let config = AppConfig::load_or_default();
@@ -55,10 +123,19 @@ impl ImageConverterApp {
             tokio_runtime: tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap(),
             progress_receiver,
             progress_sender,
+            cancel_token: CancellationToken::new(),
             menu_bar_state,
+            password_prompt: None,
         }
     }
 
+    /// 请求取消当前任务，已开始的文件会处理完，最终进度中包含部分结果
+    fn cancel_processing(&mut self) {
+        if !self.is_processing || self.cancel_token.is_cancelled() { return; }
+        self.cancel_token.cancel();
+        self.status_message = "正在取消，等待当前文件处理完成...".to_string();
+    }
+
     fn start_processing(&mut self) {
         if self.is_processing { return; }
         if self.input_path.trim().is_empty() || self.output_path.trim().is_empty() {
@@ -70,23 +147,23 @@ impl ImageConverterApp {
         self.is_error = false;
         self.progress = ProgressUpdate::default();
         self.status_message = "正在准备处理...".to_string();
-        let (input_path, output_path, config, progress_sender) = (
+        self.cancel_token = CancellationToken::new();
+        let (input_path, output_path, config, progress_sender, cancel) = (
             self.input_path.clone().into(),
             self.output_path.clone().into(),
             self.config.clone(),
             self.progress_sender.clone(),
+            self.cancel_token.clone(),
         );
         match self.config.default_app_mode {
             AppMode::ImageConverter => {
                 self.tokio_runtime.spawn(async move {
-                    BatchProcessor::process_files(
+                    Self::process_image_conversion(
                         input_path,
                         output_path,
-                        config.default_target_size,
-                        config.default_output_format,
-                        config.default_processing_mode,
-                        config.watermark_settings,
+                        config,
                         progress_sender,
+                        cancel,
                     ).await;
                 });
             },
@@ -97,6 +174,7 @@ impl ImageConverterApp {
                         output_path,
                         config,
                         progress_sender,
+                        cancel,
                     ).await;
                 });
             },
@@ -107,6 +185,7 @@ impl ImageConverterApp {
                         output_path,
                         config,
                         progress_sender,
+                        cancel,
                     ).await;
                 });
             },
@@ -117,88 +196,125 @@ impl ImageConverterApp {
                         output_path,
                         config,
                         progress_sender,
+                        cancel,
                     ).await;
                 });
             },
         }
     }
 
+    /// 显示加密PDF的密码输入框，确定后记住该文件的密码并重新开始任务
+    fn show_password_prompt(&mut self, ctx: &egui::Context) {
+        let Some(prompt) = &mut self.password_prompt else { return };
+        let mut submitted = false;
+        let mut dismissed = false;
+        egui::Window::new("🔒 需要PDF密码")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!("文件: {}", prompt.path.display()));
+                if prompt.incorrect {
+                    ui.colored_label(egui::Color32::from_rgb(220, 80, 80), "⚠ 密码不正确，请重新输入");
+                }
+                let response = ui.add(egui::TextEdit::singleline(&mut prompt.password).password(true).desired_width(220.0));
+                let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                ui.horizontal(|ui| {
+                    submitted = ui.button("确定并重试").clicked() || entered;
+                    dismissed = ui.button("取消").clicked();
+                });
+            });
+
+        if submitted && !prompt.password.is_empty() {
+            if let Some(prompt) = self.password_prompt.take() {
+                let key = prompt.path.to_string_lossy().into_owned();
+                self.config.pdf_render.file_passwords.insert(key, prompt.password);
+                self.start_processing();
+            }
+        } else if dismissed {
+            self.password_prompt = None;
+        }
+    }
+
+    /// 图片格式转换处理函数
+    async fn process_image_conversion(
+        input_path: std::path::PathBuf,
+        output_path: std::path::PathBuf,
+        config: AppConfig,
+        progress_sender: tokio::sync::mpsc::UnboundedSender<ProgressUpdate>,
+        cancel: CancellationToken,
+    ) {
+        let _ = progress_sender.send(ProgressUpdate {
+            current_file: "正在计算总任务数...".to_string(),
+            ..Default::default()
+        });
+
+        let options = BatchOptions::from_config(input_path, output_path, &config);
+        let progress_sender_clone = progress_sender.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            BatchProcessor::process_files(&options, &progress_sender_clone, &cancel)
+        }).await;
+
+        let error_message = match result {
+            Ok(Ok(_)) => return, // 完成信号已由 Finished 事件发送
+            Ok(Err(e)) => format!("处理失败: {}", e),
+            Err(_) => "处理失败: 任务执行错误".to_string(),
+        };
+        let _ = progress_sender.send(ProgressUpdate {
+            is_complete: true,
+            error_message: Some(error_message),
+            ..Default::default()
+        });
+    }
+
     /// PDF转换处理函数
     async fn process_pdf_conversion(
         input_path: std::path::PathBuf,
         output_path: std::path::PathBuf,
         config: AppConfig,
         progress_sender: tokio::sync::mpsc::UnboundedSender<ProgressUpdate>,
+        cancel: CancellationToken,
     ) {
         let _ = progress_sender.send(ProgressUpdate {
             current_file: "正在准备PDF转换...".to_string(),
             ..Default::default()
         });
 
-        // 创建升级版PDF配置
-        let pdf_config = PdfConfig {
-            output_path: {
-                let mut path = output_path.clone();
-                path.push(&config.pdf_settings.default_output_name);
-                path
-            },
-            preserve_original_size: config.pdf_settings.preserve_original_size,
-            page_orientation: match config.pdf_settings.page_orientation {
-                PdfPageOrientation::Auto => PageOrientation::Auto,
-                PdfPageOrientation::Landscape => PageOrientation::Landscape,
-                PdfPageOrientation::Portrait => PageOrientation::Portrait,
-            },
-            image_quality: config.pdf_settings.image_quality,
-            one_image_per_page: config.pdf_settings.one_image_per_page,
-            // 🚀 新增配置项
-            dpi: 300.0,                           // 高质量300 DPI
-            margin_mm: 0.0,                       // 0mm边距 - 消除白边
-            auto_rotate: true,                    // 启用自动旋转
-            page_mode: PageMode::AdaptiveSize,    // 自适应页面尺寸
-        };
-
-        // 使用修复后的PDF转换器
-        let result = tokio::task::spawn_blocking(move || -> anyhow::Result<usize> {
-            match ImageToPdfConverter::detect_input_type(&input_path) {
-                Ok(InputType::SingleImage) => {
-                    ImageToPdfConverter::convert_single_image(&input_path, &pdf_config)?;
-                    Ok(1)
-                },
-                Ok(InputType::Folder) => {
-                    let image_files = ImageToPdfConverter::get_image_files_public(&input_path)?;
-                    let total_images = image_files.len();
-                    ImageToPdfConverter::convert_folder_to_pdf(&input_path, &pdf_config)?;
-                    Ok(total_images)
-                },
-                Err(e) => Err(e),
-            }
+        let result = tokio::task::spawn_blocking(move || {
+            ImageToPdfConverter::convert(&input_path, &output_path, &config, &cancel)
         }).await;
 
-        match result {
-            Ok(Ok(total_images)) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    processed: total_images,
-                    total: total_images,
-                    is_complete: true,
-                    current_file: format!("PDF转换完成 - 处理了{}张图片", total_images),
-                    ..Default::default()
-                });
+        let update = match result {
+            Ok(Ok(Some((_, total_images)))) => ProgressUpdate {
+                processed: total_images,
+                total: total_images,
+                is_complete: true,
+                current_file: format!("PDF转换完成 - 处理了{}张图片", total_images),
+                ..Default::default()
             },
-            Ok(Err(e)) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    is_complete: true,
-                    error_message: Some(format!("PDF转换失败: {}", e)),
-                    ..Default::default()
-                });
+            Ok(Ok(None)) => ProgressUpdate {
+                is_complete: true,
+                current_file: "输出PDF已存在，已跳过".to_string(),
+                ..Default::default()
             },
-            Err(_) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    is_complete: true,
-                    error_message: Some("PDF转换失败: 未知错误".to_string()),
-                    ..Default::default()
-                });
+            Ok(Err(ConvertError::Cancelled)) => ProgressUpdate {
+                is_complete: true,
+                cancelled: true,
+                current_file: "PDF转换已取消，未生成PDF文件".to_string(),
+                ..Default::default()
             },
-        }
+            Ok(Err(e)) => ProgressUpdate {
+                is_complete: true,
+                error_message: Some(format!("PDF转换失败: {}", e)),
+                ..Default::default()
+            },
+            Err(_) => ProgressUpdate {
+                is_complete: true,
+                error_message: Some("PDF转换失败: 未知错误".to_string()),
+                ..Default::default()
+            },
+        };
+        let _ = progress_sender.send(update);
     }
 
     /// PDF转图片处理函数
@@ -207,10 +323,8 @@ impl ImageConverterApp {
         output_path: std::path::PathBuf,
         config: AppConfig,
         progress_sender: tokio::sync::mpsc::UnboundedSender<ProgressUpdate>,
+        cancel: CancellationToken,
     ) {
-        use crate::converter::pdf_converter;
-        use crate::converter::image_converter;
-
         let _ = progress_sender.send(ProgressUpdate {
             current_file: "正在准备PDF转图片...".to_string(),
             ..Default::default()
@@ -218,184 +332,30 @@ impl ImageConverterApp {
 
         let progress_sender_clone = progress_sender.clone();
         let result = tokio::task::spawn_blocking(move || {
-            let progress_sender = progress_sender_clone;
-            let dpi = config.advanced_settings.pdf_render_dpi;
-
-            // 根据处理模式确定要处理的PDF文件列表
-            let pdf_files: Vec<std::path::PathBuf> = match config.default_processing_mode {
-                ProcessingMode::SingleFile => {
-                    // 单文件模式：检查是否为PDF文件
-                    if input_path.extension().and_then(|s| s.to_str()) != Some("pdf") {
-                        return Err(anyhow::anyhow!("输入文件不是PDF格式"));
-                    }
-                    vec![input_path]
-                },
-                ProcessingMode::Folder => {
-                    // 文件夹模式：遍历文件夹中的所有PDF文件
-                    if !input_path.is_dir() {
-                        return Err(anyhow::anyhow!("输入路径不是文件夹"));
-                    }
-
-                    let mut pdf_files = Vec::new();
-                    for entry in std::fs::read_dir(&input_path)
-                        .map_err(|e| anyhow::anyhow!("无法读取文件夹 {}: {}", input_path.display(), e))?
-                    {
-                        let entry = entry?;
-                        let path = entry.path();
-                        if path.is_file() &&
-                           path.extension().and_then(|s| s.to_str()) == Some("pdf") {
-                            pdf_files.push(path);
-                        }
-                    }
-
-                    if pdf_files.is_empty() {
-                        return Err(anyhow::anyhow!("文件夹中没有找到PDF文件"));
-                    }
-
-                    // 按文件名排序，确保处理顺序一致
-                    pdf_files.sort();
-                    pdf_files
-                }
-            };
-
-            println!("🔄 开始PDF转图片转换，找到 {} 个PDF文件", pdf_files.len());
-
-            // 预计算总页数用于进度条
-            let mut total_pages = 0;
-            for pdf_file in pdf_files.iter() {
-                match pdf_converter::get_pdf_page_count(pdf_file) {
-                    Ok(count) => {
-                        total_pages += count;
-                        println!("📄 PDF文件 {} 有 {} 页", pdf_file.display(), count);
-                    },
-                    Err(e) => {
-                        eprintln!("⚠️  无法获取PDF页数 {}: {}，跳过", pdf_file.display(), e);
-                    }
-                }
-            }
-
-            // 发送总页数用于进度条显示
-            progress_sender.send(ProgressUpdate {
-                total: total_pages,
-                current_file: format!("开始处理 {} 个PDF文件，共 {} 页", pdf_files.len(), total_pages),
-                ..Default::default()
-            })?;
-
-            // 确保输出目录存在
-            std::fs::create_dir_all(&output_path)?;
-
-            let mut total_processed = 0;
-            for (file_index, pdf_file) in pdf_files.iter().enumerate() {
-                println!("📄 处理第 {} 个PDF: {}", file_index + 1, pdf_file.display());
-
-                let images = pdf_converter::convert_pdf_to_images(pdf_file, dpi)?;
-                println!("✅ 成功渲染 {} 页", images.len());
-
-                // 为每个PDF文件创建子文件夹（如果是批量处理）
-                let file_output_dir = if pdf_files.len() > 1 {
-                    let file_stem = pdf_file.file_stem()
-                        .and_then(|s| s.to_str())
-                        .unwrap_or("unknown");
-                    output_path.join(file_stem)
-                } else {
-                    output_path.clone()
-                };
-
-                std::fs::create_dir_all(&file_output_dir)?;
-
-                // 基于libavif策略：批量并行处理，但控制并发数
-                let num_cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
-                let _max_parallel = (num_cores * 3 / 4).max(2).min(8); // 限制最大并发数
-
-                use std::sync::{Arc, Mutex};
-                use std::sync::atomic::{AtomicUsize, Ordering};
-
-                let processed_counter = Arc::new(AtomicUsize::new(0));
-                let progress_sender_shared = Arc::new(Mutex::new(progress_sender.clone()));
-
-                // 🚀 高性能分块并行处理 - 基于现代图像处理技术
-                use rayon::prelude::*;
-
-                // 动态分块大小：根据内存和CPU核心数优化
-                let chunk_size = if images.len() > 100 {
-                    (num_cores * 4).max(8).min(32) // 大量页面：更大块
-                } else {
-                    (num_cores * 2).max(4).min(16) // 少量页面：较小块
-                };
-
-                println!("🧠 智能分块处理：{} 页面分为 {} 大小的块，使用 {} 个CPU核心",
-                         images.len(), chunk_size, num_cores);
-
-                // 分块并行处理 - 避免内存峰值
-                images.par_chunks(chunk_size).enumerate().try_for_each(|(chunk_idx, chunk)| -> anyhow::Result<()> {
-                    chunk.par_iter().enumerate().try_for_each(|(local_page_num, image)| -> anyhow::Result<()> {
-                        let global_page_num = chunk_idx * chunk_size + local_page_num;
-                        let output_file = file_output_dir.join(format!("page_{:03}.{}",
-                            global_page_num + 1,
-                            config.default_output_format.extension()
-                        ));
-
-                        // 使用配置的输出格式和质量设置
-                        image_converter::compress_and_save(
-                            image,
-                            &output_file,
-                            config.default_target_size,
-                            config.default_output_format
-                        )?;
-
-                        // 原子更新计数器
-                        let current_processed = processed_counter.fetch_add(1, Ordering::SeqCst) + 1;
-
-                        // 安全发送进度更新（每10页更新一次，减少锁竞争）
-                        if current_processed % 10 == 0 || current_processed == images.len() {
-                            if let Ok(sender) = progress_sender_shared.lock() {
-                                let _ = sender.send(ProgressUpdate {
-                                    processed: total_processed + current_processed,
-                                    total: total_pages,
-                                current_file: format!("并行处理 {} ({}/{}页)",
-                                                    pdf_file.file_name().unwrap_or_default().to_string_lossy(),
-                                                    current_processed, images.len()),
-                                ..Default::default()
-                            });
-                        }
-                    }
-
-                        Ok(())
-                    })
-                })?;
-
-                total_processed += images.len();
-            }
-
-            println!("🎉 PDF转图片完成! 共处理 {} 页", total_processed);
-            Ok(total_processed)
+            PdfToImageConverter::process_files(&input_path, &output_path, &config, &progress_sender_clone, &cancel)
         }).await;
 
-        match result {
-            Ok(Ok(count)) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    processed: count,
-                    total: count,
-                    is_complete: true,
-                    current_file: format!("PDF转图片完成，共生成 {} 张图片", count),
-                    ..Default::default()
-                });
-            },
+        let (error_message, password_needed) = match result {
+            Ok(Ok(_)) => return, // 完成信号已由 Finished 事件发送
             Ok(Err(e)) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    is_complete: true,
-                    error_message: Some(format!("PDF转图片失败: {}", e)),
-                    ..Default::default()
-                });
-            },
-            Err(_) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    is_complete: true,
-                    error_message: Some("PDF转图片失败: 任务执行错误".to_string()),
-                    ..Default::default()
-                });
+                let password_needed = match &e {
+                    ConvertError::PdfPasswordRequired { path } | ConvertError::PdfPasswordIncorrect { path } => Some(path.clone()),
+                    _ => None,
+                };
+                let mut message = format!("PDF转图片失败: {}", e);
+                if matches!(e, ConvertError::PdfiumUnavailable { .. }) {
+                    message.push_str(PDFIUM_INSTALL_HELP);
+                }
+                (message, password_needed)
             },
-        }
+            Err(_) => ("PDF转图片失败: 任务执行错误".to_string(), None),
+        };
+        let _ = progress_sender.send(ProgressUpdate {
+            is_complete: true,
+            error_message: Some(error_message),
+            password_needed,
+            ..Default::default()
+        });
     }
 
     /// 纯水印处理函数（不压缩，保持原画质）
@@ -404,9 +364,8 @@ impl ImageConverterApp {
         output_path: std::path::PathBuf,
         config: AppConfig,
         progress_sender: tokio::sync::mpsc::UnboundedSender<ProgressUpdate>,
+        cancel: CancellationToken,
     ) {
-        use crate::converter::simple_watermark::SimpleWatermarkProcessor;
-
         let _ = progress_sender.send(ProgressUpdate {
             current_file: "正在准备纯水印处理...".to_string(),
             ..Default::default()
@@ -414,171 +373,19 @@ impl ImageConverterApp {
 
         let progress_sender_clone = progress_sender.clone();
         let result = tokio::task::spawn_blocking(move || {
-            let progress_sender = progress_sender_clone;
-            // 检查是否启用了水印
-            if !config.watermark_settings.enable_text_watermark && !config.watermark_settings.enable_image_watermark {
-                return Err(anyhow::anyhow!("请至少启用一种水印类型"));
-            }
-
-            // 获取要处理的图片文件列表
-            let image_files: Vec<std::path::PathBuf> = match config.default_processing_mode {
-                ProcessingMode::SingleFile => {
-                    // 单文件模式：检查是否为支持的图片格式
-                    if let Some(ext) = input_path.extension().and_then(|s| s.to_str()) {
-                        let ext = ext.to_lowercase();
-                        if matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "webp" | "bmp" | "tiff") {
-                            vec![input_path]
-                        } else {
-                            return Err(anyhow::anyhow!("不支持的图片格式: {}", ext));
-                        }
-                    } else {
-                        return Err(anyhow::anyhow!("无法识别文件格式"));
-                    }
-                },
-                ProcessingMode::Folder => {
-                    // 文件夹模式：遍历文件夹中的所有图片文件
-                    if !input_path.is_dir() {
-                        return Err(anyhow::anyhow!("输入路径不是文件夹"));
-                    }
-
-                    let mut image_files = Vec::new();
-                    for entry in std::fs::read_dir(&input_path)
-                        .map_err(|e| anyhow::anyhow!("无法读取文件夹 {}: {}", input_path.display(), e))?
-                    {
-                        let entry = entry?;
-                        let path = entry.path();
-                        if path.is_file() {
-                            if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-                                let ext = ext.to_lowercase();
-                                if matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "webp" | "bmp" | "tiff") {
-                                    image_files.push(path);
-                                }
-                            }
-                        }
-                    }
-
-                    if image_files.is_empty() {
-                        return Err(anyhow::anyhow!("文件夹中没有找到支持的图片文件"));
-                    }
-
-                    // 按文件名排序，确保处理顺序一致
-                    image_files.sort();
-                    image_files
-                }
-            };
-
-            println!("💧 开始纯水印处理，找到 {} 个图片文件", image_files.len());
-
-            // 发送总文件数进度更新
-            let total_files = image_files.len();
-            let _ = progress_sender.send(ProgressUpdate {
-                processed: 0,
-                total: total_files,
-                current_file: format!("准备处理 {} 个文件...", total_files),
-                ..Default::default()
-            });
-
-            // 确保输出目录存在
-            std::fs::create_dir_all(&output_path)?;
-
-            let watermark_processor = SimpleWatermarkProcessor::new();
-            let mut processed_count = 0;
-
-            for (file_index, image_file) in image_files.iter().enumerate() {
-                println!("🖼️ 处理第 {} 个图片: {}", file_index + 1, image_file.display());
-
-                // 发送当前处理文件的进度更新
-                let file_name = image_file.file_name().unwrap_or_default().to_string_lossy().to_string();
-                let _ = progress_sender.send(ProgressUpdate {
-                    processed: file_index,
-                    total: total_files,
-                    current_file: format!("正在处理: {}", file_name),
-                    ..Default::default()
-                });
-
-                // 加载原始图片
-                let original_image = image::open(image_file)
-                    .map_err(|e| anyhow::anyhow!("无法打开图片 '{}': {}", image_file.display(), e))?;
-
-                let mut processed_image = original_image.clone();
-
-                // 添加文字水印
-                if config.watermark_settings.enable_text_watermark {
-                    let text_watermark = config.watermark_settings.to_text_watermark();
-                    processed_image = watermark_processor.add_text_watermark(processed_image, &text_watermark)?;
-                }
-
-                // 添加图片水印
-                if config.watermark_settings.enable_image_watermark && !config.watermark_settings.image_watermark_path.is_empty() {
-                    let image_watermark = config.watermark_settings.to_image_watermark();
-                    processed_image = watermark_processor.add_image_watermark(processed_image, &image_watermark)?;
-                }
-
-                // 保存处理后的图片（保持原始格式和质量）
-                let file_name = image_file.file_name().unwrap_or_default();
-                let output_file = output_path.join(file_name);
-
-                // 🔧 智能保存：检测文件格式并处理RGBA兼容性
-                let file_extension = output_file.extension()
-                    .and_then(|ext| ext.to_str())
-                    .unwrap_or("")
-                    .to_lowercase();
-
-                let final_image = if file_extension == "jpg" || file_extension == "jpeg" {
-                    // JPEG不支持透明度，转换为RGB
-                    match processed_image {
-                        DynamicImage::ImageRgba8(_) => DynamicImage::ImageRgb8(processed_image.to_rgb8()),
-                        _ => processed_image,
-                    }
-                } else {
-                    processed_image // PNG等其他格式支持RGBA
-                };
-
-                final_image.save(&output_file)
-                    .map_err(|e| anyhow::anyhow!("保存图片失败 '{}': {}", output_file.display(), e))?;
-
-                processed_count += 1;
-                println!("✨ 已保存水印图片: {}", output_file.display());
-
-                // 发送文件完成进度更新
-                let output_file_name = output_file.file_name().unwrap_or_default().to_string_lossy().to_string();
-                let _ = progress_sender.send(ProgressUpdate {
-                    processed: file_index + 1,
-                    total: total_files,
-                    current_file: format!("已完成: {} ({}/{})", output_file_name, file_index + 1, total_files),
-                    ..Default::default()
-                });
-            }
-
-            println!("🎉 纯水印处理完成! 共处理 {} 张图片", processed_count);
-            Ok(processed_count)
+            PureWatermarkProcessor::process_files(&input_path, &output_path, &config, &progress_sender_clone, &cancel)
         }).await;
 
-        match result {
-            Ok(Ok(count)) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    processed: count,
-                    total: count,
-                    is_complete: true,
-                    current_file: format!("纯水印处理完成，共处理 {} 张图片", count),
-                    ..Default::default()
-                });
-            },
-            Ok(Err(e)) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    is_complete: true,
-                    error_message: Some(format!("纯水印处理失败: {}", e)),
-                    ..Default::default()
-                });
-            },
-            Err(_) => {
-                let _ = progress_sender.send(ProgressUpdate {
-                    is_complete: true,
-                    error_message: Some("纯水印处理失败: 任务执行错误".to_string()),
-                    ..Default::default()
-                });
-            },
-        }
+        let error_message = match result {
+            Ok(Ok(_)) => return, // 完成信号已由 Finished 事件发送
+            Ok(Err(e)) => format!("纯水印处理失败: {}", e),
+            Err(_) => "纯水印处理失败: 任务执行错误".to_string(),
+        };
+        let _ = progress_sender.send(ProgressUpdate {
+            is_complete: true,
+            error_message: Some(error_message),
+            ..Default::default()
+        });
     }
 
     /// 显示图片转换设置界面
@@ -594,7 +401,7 @@ impl ImageConverterApp {
 
                 // 根据格式动态显示不同的控件
                 match self.config.default_output_format {
-                    OutputFormat::Jpeg | OutputFormat::PngCompressed | OutputFormat::WebPLossy => {
+                    OutputFormat::Jpeg | OutputFormat::PngCompressed | OutputFormat::WebPLossy | OutputFormat::Avif => {
                         // 显示目标大小控件
                         components::number_input_with_unit(ui, "目标大小", &mut self.config.default_target_size, "KB", 10, 10240);
                     },
@@ -619,6 +426,219 @@ impl ImageConverterApp {
                 ui.add_space(20.0);
                 components::format_selector(ui, "处理模式", &mut self.config.default_processing_mode, &ProcessingMode::all_modes());
             });
+
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                components::format_selector(ui, "元数据", &mut self.config.metadata_policy, &MetadataPolicy::all_policies());
+            });
+
+            ui.add_space(5.0);
+            Self::show_resize_settings(ui, &mut self.config.resize_settings);
+
+            ui.add_space(5.0);
+            Self::show_naming_settings(ui, &mut self.config.naming, true, true);
+
+            ui.add_space(5.0);
+            Self::show_output_settings(ui, &mut self.config.output_settings, &mut self.config.advanced_settings.keep_original_files, true);
+            ui.checkbox(&mut self.config.resume_batch, "继续上次的任务")
+                .on_hover_text("按输出文件夹中的任务日志跳过已完成的文件；设置改变后会重新处理");
+            Self::show_concurrency_settings(ui, &mut self.config.advanced_settings);
+
+            if self.config.default_processing_mode == ProcessingMode::Folder {
+                ui.add_space(5.0);
+                Self::show_folder_settings(ui, &mut self.config.folder_settings);
+                Self::show_discovery_settings(ui, &mut self.config.discovery);
+            }
+
+            match self.config.default_output_format {
+                OutputFormat::Jpeg => {
+                    ui.add_space(5.0);
+                    Self::show_jpeg_encoder_settings(ui, &mut self.config.advanced_settings.jpeg_encoder);
+                },
+                OutputFormat::PngCompressed => {
+                    ui.add_space(5.0);
+                    Self::show_png_quantization_settings(ui, &mut self.config.advanced_settings.png_quantization);
+                },
+                _ => {}
+            }
+        });
+    }
+
+    /// 显示尺寸调整设置
+    fn show_resize_settings(ui: &mut egui::Ui, resize: &mut ResizeSettings) {
+        ui.horizontal(|ui| {
+            components::format_selector(ui, "尺寸调整", &mut resize.mode, &ResizeMode::all_modes());
+        });
+
+        ui.horizontal(|ui| {
+            match resize.mode {
+                ResizeMode::Off => {},
+                ResizeMode::MaxSize | ResizeMode::Exact => {
+                    components::number_input_with_unit(ui, "宽度", &mut resize.width, "px", 0, 20000);
+                    ui.add_space(10.0);
+                    components::number_input_with_unit(ui, "高度", &mut resize.height, "px", 0, 20000);
+                    if resize.mode == ResizeMode::Exact {
+                        ui.add_space(20.0);
+                        components::format_selector(ui, "适应方式", &mut resize.fit, &FitMode::all_modes());
+                    } else {
+                        ui.label("💡 0 表示不限制");
+                    }
+                },
+                ResizeMode::Percentage => {
+                    ui.label("缩放比例:");
+                    ui.add(egui::Slider::new(&mut resize.percentage, 1.0..=400.0).suffix("%"));
+                },
+            }
+        });
+    }
+
+    /// 显示批处理并发数和解码内存上限
+    fn show_concurrency_settings(ui: &mut egui::Ui, advanced: &mut AdvancedSettings) {
+        ui.horizontal(|ui| {
+            ui.label("并发数:");
+            ui.add(egui::DragValue::new(&mut advanced.max_concurrent_jobs).speed(0.1).clamp_range(0..=64));
+            ui.add_space(10.0);
+            ui.label("内存上限:");
+            ui.add(egui::DragValue::new(&mut advanced.memory_limit_mb).speed(16.0).clamp_range(0..=262144).suffix(" MB"));
+            ui.label("💡 0 表示自动");
+        });
+    }
+
+    /// 显示PDF页面渲染选项（灰度、背景、注释和表单域）
+    fn show_pdf_render_settings(ui: &mut egui::Ui, render: &mut PdfRenderSettings) {
+        ui.horizontal(|ui| {
+            components::format_selector(ui, "页面背景", &mut render.background, &PageBackground::all_backgrounds());
+            if render.background == PageBackground::Color {
+                ui.color_edit_button_srgb(&mut render.background_color);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut render.grayscale, "灰度渲染");
+            ui.add_space(10.0);
+            ui.checkbox(&mut render.render_annotations, "渲染注释");
+            ui.add_space(10.0);
+            ui.checkbox(&mut render.render_form_fields, "渲染表单域")
+                .on_hover_text("包括表单中填写的内容和签名域，关闭后只渲染页面本身");
+        });
+        if render.background == PageBackground::Transparent {
+            ui.label("💡 透明背景只在PNG、WebP、AVIF中保留，JPEG输出为白底");
+        }
+    }
+
+    /// 显示PDF文本提取设置
+    fn show_pdf_text_settings(ui: &mut egui::Ui, text: &mut PdfTextSettings) {
+        ui.horizontal(|ui| {
+            components::format_selector(ui, "提取文本", &mut text.output, &TextOutput::all_outputs());
+            if text.output != TextOutput::Off {
+                ui.add_space(10.0);
+                ui.checkbox(&mut text.word_boxes, "输出单词位置 (JSON)")
+                    .on_hover_text("同时输出包含每个单词边界框的JSON文件，坐标单位为PDF点，原点在页面左下角");
+            }
+        });
+    }
+
+    /// 显示文件夹模式设置（子文件夹遍历和输出目录结构）
+    fn show_folder_settings(ui: &mut egui::Ui, folder: &mut FolderSettings) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut folder.recursive, "包含子文件夹");
+            if folder.recursive {
+                ui.add_space(10.0);
+                ui.label("最大深度:");
+                ui.add(egui::DragValue::new(&mut folder.max_depth).speed(0.1).clamp_range(0..=32));
+                ui.label("💡 0 表示不限制");
+                ui.add_space(10.0);
+                ui.checkbox(&mut folder.preserve_structure, "保留子目录结构");
+            }
+        });
+    }
+
+    /// 显示文件夹输入的过滤规则，每行一个 glob 模式
+    fn show_discovery_settings(ui: &mut egui::Ui, discovery: &mut DiscoverySettings) {
+        ui.horizontal(|ui| {
+            for (label, patterns, hint) in [
+                ("只包含:", &mut discovery.include, "*.jpg"),
+                ("排除:", &mut discovery.exclude, "raw/**"),
+            ] {
+                ui.label(label);
+                let mut text = patterns.join("\n");
+                let editor = egui::TextEdit::multiline(&mut text).desired_rows(1).desired_width(140.0).hint_text(hint);
+                if ui.add(editor).changed() {
+                    *patterns = text.split('\n').map(str::to_string).collect();
+                }
+                ui.add_space(10.0);
+            }
+            ui.checkbox(&mut discovery.include_hidden, "包含隐藏文件");
+        });
+        ui.label("💡 每行一个模式，匹配文件名或相对路径；文件格式按内容识别");
+    }
+
+    /// 显示输出文件命名模板设置
+    fn show_naming_settings(ui: &mut egui::Ui, naming: &mut NamingSettings, images: bool, pdf_pages: bool) {
+        ui.horizontal(|ui| {
+            if images {
+                ui.label("文件名模板:");
+                ui.add(egui::TextEdit::singleline(&mut naming.image_template).desired_width(150.0));
+                ui.add_space(10.0);
+            }
+            if pdf_pages {
+                ui.label("PDF页面模板:");
+                ui.add(egui::TextEdit::singleline(&mut naming.pdf_page_template).desired_width(180.0));
+            }
+            if ui.label("ℹ️").hovered() {
+                egui::show_tooltip_text(ui.ctx(), egui::Id::new("naming_tooltip"),
+                    format!("可用占位符:\n{}\n\n扩展名由输出格式自动添加", TEMPLATE_TOKENS));
+            }
+        });
+    }
+
+    /// 显示输出冲突设置，`allow_replace` 为 false 的模式（PDF输出）不提供替换原文件
+    fn show_output_settings(ui: &mut egui::Ui, output: &mut OutputSettings, keep_originals: &mut bool, allow_replace: bool) {
+        ui.horizontal(|ui| {
+            components::format_selector(ui, "文件已存在", &mut output.collision, &CollisionPolicy::all_policies());
+            if allow_replace {
+                ui.add_space(20.0);
+                ui.checkbox(&mut output.replace_originals, "替换原文件");
+                if output.replace_originals {
+                    ui.checkbox(keep_originals, "备份原文件");
+                }
+            }
+        });
+        if allow_replace && output.replace_originals {
+            ui.label(if *keep_originals {
+                "💡 输出写回源文件所在目录，原文件移到 .image_converter_backup 文件夹"
+            } else {
+                "⚠️ 输出写回源文件所在目录，原文件将被删除"
+            });
+        }
+    }
+
+    /// 显示JPEG编码器设置
+    fn show_jpeg_encoder_settings(ui: &mut egui::Ui, encoder: &mut JpegEncoderSettings) {
+        ui.horizontal(|ui| {
+            components::format_selector(ui, "JPEG编码器", &mut encoder.backend, &JpegBackend::all_backends());
+        });
+
+        if encoder.backend == JpegBackend::Mozjpeg {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut encoder.progressive, "渐进式");
+                ui.checkbox(&mut encoder.trellis, "网格量化");
+                ui.checkbox(&mut encoder.optimize_huffman, "优化霍夫曼表");
+                ui.add_space(20.0);
+                components::format_selector(ui, "色度子采样", &mut encoder.chroma_subsampling, &ChromaSubsampling::all_modes());
+            });
+        }
+    }
+
+    /// 显示PNG调色板量化设置
+    fn show_png_quantization_settings(ui: &mut egui::Ui, quant: &mut PngQuantOptions) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut quant.enabled, "调色板量化");
+            if quant.enabled {
+                ui.add_space(10.0);
+                ui.label("最大颜色数:");
+                ui.add(egui::Slider::new(&mut quant.max_colors, 2..=256));
+                ui.checkbox(&mut quant.dithering, "抖动");
+            }
         });
     }
 
@@ -651,6 +671,12 @@ impl ImageConverterApp {
                 ui.label("输出文件名:");
                 ui.add(egui::TextEdit::singleline(&mut self.config.pdf_settings.default_output_name).desired_width(200.0));
             });
+
+            ui.add_space(5.0);
+            Self::show_discovery_settings(ui, &mut self.config.discovery);
+
+            ui.add_space(5.0);
+            Self::show_output_settings(ui, &mut self.config.output_settings, &mut self.config.advanced_settings.keep_original_files, false);
         });
     }
 
@@ -663,22 +689,86 @@ impl ImageConverterApp {
             ui.horizontal(|ui| {
                 components::format_selector(ui, "处理模式", &mut self.config.default_processing_mode, &ProcessingMode::all_modes());
                 ui.add_space(20.0);
-                components::format_selector(ui, "输出格式", &mut self.config.default_output_format, &OutputFormat::all_formats());
+                components::format_selector(ui, "输出内容", &mut self.config.pdf_render.output, &PdfOutput::all_outputs());
             });
 
-            ui.add_space(10.0);
+            // 提取内嵌图片时原样写出图片数据，输出格式、缩放和渲染设置都不适用
+            let rendering = self.config.pdf_render.output == PdfOutput::RenderPages;
+            if rendering {
+                ui.horizontal(|ui| {
+                    components::format_selector(ui, "输出格式", &mut self.config.default_output_format, &OutputFormat::all_formats());
+                });
+
+                ui.add_space(5.0);
+                Self::show_resize_settings(ui, &mut self.config.resize_settings);
+            } else {
+                ui.label("💡 图片按原始分辨率保存：JPEG和JPEG 2000原样导出，其他编码无损保存为PNG；文件名追加图片序号 _img_N");
+            }
+
+            ui.add_space(5.0);
+            Self::show_naming_settings(ui, &mut self.config.naming, false, true);
+
+            ui.add_space(5.0);
+            Self::show_output_settings(ui, &mut self.config.output_settings, &mut self.config.advanced_settings.keep_original_files, false);
+
+            if self.config.default_processing_mode == ProcessingMode::Folder {
+                ui.add_space(5.0);
+                Self::show_folder_settings(ui, &mut self.config.folder_settings);
+                Self::show_discovery_settings(ui, &mut self.config.discovery);
+            }
+
+            if rendering {
+                ui.add_space(5.0);
+                Self::show_pdf_render_settings(ui, &mut self.config.pdf_render);
+
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    let render = &mut self.config.pdf_render;
+                    components::format_selector(ui, "渲染尺寸", &mut render.size_mode, &RenderSize::all_modes());
+                    ui.add_space(10.0);
+                    match render.size_mode {
+                        RenderSize::Dpi => {
+                            ui.add(egui::Slider::new(&mut self.config.advanced_settings.pdf_render_dpi, 72.0..=600.0).text("DPI"));
+
+                            // 添加DPI说明提示
+                            ui.label("💡");
+                            if ui.label("ℹ️").hovered() {
+                                egui::show_tooltip_text(ui.ctx(), egui::Id::new("dpi_tooltip"),
+                                    "DPI设置说明:\n• 72 DPI: 网页显示质量，文件小\n• 150 DPI: 普通打印质量 (推荐)\n• 300 DPI: 高质量打印\n• 600 DPI: 超高质量，文件大");
+                            }
+                        },
+                        RenderSize::Width => {
+                            components::number_input_with_unit(ui, "宽度", &mut render.target_width, "px", 1, 20000);
+                        },
+                        RenderSize::Height => {
+                            components::number_input_with_unit(ui, "高度", &mut render.target_height, "px", 1, 20000);
+                        },
+                        RenderSize::Fit => {
+                            components::number_input_with_unit(ui, "宽度", &mut render.target_width, "px", 1, 20000);
+                            ui.add_space(10.0);
+                            components::number_input_with_unit(ui, "高度", &mut render.target_height, "px", 1, 20000);
+                        },
+                    }
+                });
+            }
+
+            ui.add_space(5.0);
+            Self::show_pdf_text_settings(ui, &mut self.config.pdf_text);
 
             ui.horizontal(|ui| {
-                ui.label("渲染DPI:");
-                ui.add(egui::Slider::new(&mut self.config.advanced_settings.pdf_render_dpi, 72.0..=600.0).text("DPI"));
-
-                // 添加DPI说明提示
-                ui.label("💡");
-                if ui.label("ℹ️").hovered() {
-                    egui::show_tooltip_text(ui.ctx(), egui::Id::new("dpi_tooltip"),
-                        "DPI设置说明:\n• 72 DPI: 网页显示质量，文件小\n• 150 DPI: 普通打印质量 (推荐)\n• 300 DPI: 高质量打印\n• 600 DPI: 超高质量，文件大");
+                ui.label("页面范围:");
+                let range_valid = self.config.pdf_render.pages().is_ok();
+                let editor = egui::TextEdit::singleline(&mut self.config.pdf_render.page_range)
+                    .desired_width(100.0)
+                    .hint_text("全部，如 1-3,7,10-");
+                ui.add(editor).on_hover_text("逗号分隔的页码和区间，10- 表示第10页到最后一页");
+                if !range_valid {
+                    ui.colored_label(egui::Color32::from_rgb(220, 80, 80), "⚠ 格式错误");
                 }
 
+                if !rendering {
+                    return;
+                }
                 ui.add_space(20.0);
 
                 // 根据输出格式显示不同的控件
@@ -769,6 +859,23 @@ impl ImageConverterApp {
                 components::format_selector(ui, "处理模式", &mut self.config.default_processing_mode, &ProcessingMode::all_modes());
             });
 
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                components::format_selector(ui, "元数据", &mut self.config.metadata_policy, &MetadataPolicy::all_policies());
+            });
+
+            ui.add_space(5.0);
+            Self::show_naming_settings(ui, &mut self.config.naming, true, false);
+
+            ui.add_space(5.0);
+            Self::show_output_settings(ui, &mut self.config.output_settings, &mut self.config.advanced_settings.keep_original_files, true);
+
+            if self.config.default_processing_mode == ProcessingMode::Folder {
+                ui.add_space(5.0);
+                Self::show_folder_settings(ui, &mut self.config.folder_settings);
+                Self::show_discovery_settings(ui, &mut self.config.discovery);
+            }
+
             ui.add_space(10.0);
 
             ui.label(styles::subheading_text("🎨 输出说明："));
@@ -923,12 +1030,21 @@ impl eframe::App for ImageConverterApp {
         if update_count > 0 {
             if self.progress.is_complete {
                 self.is_processing = false;
-                self.status_message = format!("处理完成！成功: {}, 失败: {}.", self.progress.processed, self.progress.failed);
+                self.status_message = if self.progress.cancelled {
+                    let skipped = self.progress.total.saturating_sub(self.progress.processed + self.progress.failed);
+                    format!("已取消！成功: {}, 失败: {}, 未处理: {}.", self.progress.processed, self.progress.failed, skipped)
+                } else {
+                    format!("处理完成！成功: {}, 失败: {}.", self.progress.processed, self.progress.failed)
+                };
                 if let Some(err) = &self.progress.error_message {
                     self.status_message.push_str(&format!(" 出现错误: {}", err));
                     self.is_error = true;
                 }
-            } else if self.is_processing {
+                if let Some(path) = self.progress.password_needed.take() {
+                    let incorrect = self.config.pdf_render.password_for(&path).is_some();
+                    self.password_prompt = Some(PasswordPrompt { path, password: String::new(), incorrect });
+                }
+            } else if self.is_processing && !self.cancel_token.is_cancelled() {
                 let current_progress = self.progress.processed + self.progress.failed;
                 self.status_message = format!("正在处理: {} ({}/{})",
                     self.progress.current_file,
@@ -1041,6 +1157,9 @@ impl eframe::App for ImageConverterApp {
             ui.horizontal(|ui| {
                 let start_button = ui.add_enabled(!self.is_processing, egui::Button::new(styles::heading_text("🚀 开始转换")).min_size([150.0, 40.0].into()));
                 if start_button.clicked() { self.start_processing(); }
+                let cancel_enabled = self.is_processing && !self.cancel_token.is_cancelled();
+                let cancel_button = ui.add_enabled(cancel_enabled, egui::Button::new("⏹ 取消").min_size([80.0, 40.0].into()));
+                if cancel_button.clicked() { self.cancel_processing(); }
                 if components::secondary_button(ui, "📂 打开输出文件夹").clicked() {
                     if !self.output_path.is_empty() { let _ = file_utils::open_folder_in_explorer(Path::new(&self.output_path)); }
                 }
@@ -1060,6 +1179,8 @@ impl eframe::App for ImageConverterApp {
             }
         });
 
+        self.show_password_prompt(ctx);
+
         // 优化重绘策略，只在必要时重绘
         if self.is_processing {
             // 处理过程中每500ms重绘一次，而不是每帧重绘
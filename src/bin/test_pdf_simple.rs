@@ -1,5 +1,6 @@
 // 简单的PDF转换测试
 
+use image_converter::converter::cancel::CancellationToken;
 use image_converter::converter::image_to_pdf::{ImageToPdfConverter, PdfConfig, PageOrientation, PageMode};
 use std::path::PathBuf;
 
@@ -26,6 +27,7 @@ fn main() {
         margin_mm: 0.0,
         auto_rotate: true,
         page_mode: PageMode::AdaptiveSize,
+        discovery: Default::default(),
     };
 
     println!("📄 PDF配置:");
@@ -34,7 +36,7 @@ fn main() {
     println!("  - 图片质量: {}%", config.image_quality);
 
     // 执行转换
-    match ImageToPdfConverter::convert_single_image(&PathBuf::from(test_image), &config) {
+    match ImageToPdfConverter::convert_single_image(&PathBuf::from(test_image), &config, &CancellationToken::new()) {
         Ok(()) => {
             println!("🎉 转换成功！");
             println!("📄 输出文件: {}", config.output_path.display());
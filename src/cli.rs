@@ -0,0 +1,847 @@
+// 命令行模式 - 无界面运行全部功能，便于脚本和服务器调用
+
+use crate::converter::batch_processor::{BatchOptions, BatchProcessor, BatchSummary};
+use crate::converter::cancel::CancellationToken;
+use crate::converter::error::ConvertError;
+use crate::converter::image_to_pdf::ImageToPdfConverter;
+use crate::converter::output_guard::CollisionPolicy;
+use crate::converter::pdf_converter::{PageBackground, PdfOutput, RenderSize};
+use crate::converter::pdf_text::TextOutput;
+use crate::converter::pdf_to_image::PdfToImageConverter;
+use crate::converter::progress::{describe_task, ProgressEvent, ProgressSink};
+use crate::converter::pure_watermark::PureWatermarkProcessor;
+use crate::converter::simple_watermark::WatermarkPosition;
+use crate::utils::config::{AppConfig, AppMode, OutputFormat, PdfPageOrientation, ProcessingMode};
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use pdfium_render::prelude::Pdfium;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// 全部成功
+pub const EXIT_OK: i32 = 0;
+/// 任务失败（配置错误、PDFium缺失等）
+pub const EXIT_FAILURE: i32 = 1;
+/// 参数错误（与clap保持一致）
+pub const EXIT_USAGE: i32 = 2;
+/// 任务完成，但有部分文件失败
+pub const EXIT_PARTIAL: i32 = 3;
+/// 被 Ctrl+C 取消（与shell的SIGINT约定一致）
+pub const EXIT_CANCELLED: i32 = 130;
+
+#[derive(Debug, Parser)]
+#[command(name = "image_converter", version, about = "图片格式转换工具 - 命令行模式")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// 图片格式转换与压缩
+    Convert {
+        #[command(flatten)]
+        common: CommonArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        #[command(flatten)]
+        watermark: WatermarkArgs,
+        #[command(flatten)]
+        pdf: PdfArgs,
+        /// 按输出文件夹中的任务日志继续上次中断的任务
+        #[arg(long)]
+        resume: bool,
+        /// 同时处理的最大文件数，0 表示使用全部CPU核心
+        #[arg(short = 'j', long)]
+        jobs: Option<usize>,
+        /// 同时解码图片占用的内存上限（MB），0 表示自动
+        #[arg(long, value_name = "MB")]
+        memory_limit: Option<usize>,
+    },
+    /// 图片转PDF
+    ToPdf {
+        #[command(flatten)]
+        common: CommonArgs,
+        /// 输出PDF文件名
+        #[arg(long)]
+        name: Option<String>,
+        /// 页面方向
+        #[arg(long, value_enum)]
+        orientation: Option<CliOrientation>,
+        /// 图片质量 (10-100)
+        #[arg(long, value_parser = clap::value_parser!(u8).range(10..=100))]
+        quality: Option<u8>,
+    },
+    /// PDF转图片
+    PdfToImage {
+        #[command(flatten)]
+        common: CommonArgs,
+        #[command(flatten)]
+        output: OutputArgs,
+        #[command(flatten)]
+        pdf: PdfArgs,
+        /// 渲染DPI (72-600)
+        #[arg(long)]
+        dpi: Option<f32>,
+        /// 按原始分辨率和编码导出页面中的内嵌图片，不渲染页面
+        #[arg(long)]
+        extract_images: bool,
+    },
+    /// 纯水印模式（保持原格式和画质）
+    Watermark {
+        #[command(flatten)]
+        common: CommonArgs,
+        #[command(flatten)]
+        watermark: WatermarkArgs,
+    },
+}
+
+/// 所有子命令共用的参数
+#[derive(Debug, Args)]
+struct CommonArgs {
+    /// 输入文件或文件夹（文件夹自动使用文件夹模式）
+    input: PathBuf,
+    /// 输出文件夹
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// AppConfig JSON配置文件，命令行参数会覆盖其中的设置
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// 文件夹模式下不处理子文件夹
+    #[arg(long)]
+    no_recursive: bool,
+    /// 文件夹模式的最大遍历深度（1 表示只处理输入文件夹本身）
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    max_depth: Option<u32>,
+    /// 所有结果直接写入输出文件夹，不重建子目录结构
+    #[arg(long)]
+    flat: bool,
+    /// 文件夹模式只处理匹配的文件（glob，可重复）
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+    /// 文件夹模式跳过匹配的文件（glob，可重复）
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+    /// 文件夹模式处理隐藏文件
+    #[arg(long)]
+    hidden: bool,
+}
+
+/// 输出格式相关参数
+#[derive(Debug, Args)]
+struct OutputArgs {
+    /// 输出格式
+    #[arg(short, long, value_enum)]
+    format: Option<CliFormat>,
+    /// 目标文件大小（KB）
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    target_kb: Option<u32>,
+    /// 图片输出文件名模板，如 "{stem}_{width}x{height}"
+    #[arg(long)]
+    name_template: Option<String>,
+    /// PDF页面输出文件名模板，如 "{stem}_page_{page:03}"
+    #[arg(long)]
+    page_template: Option<String>,
+    /// 输出文件已存在时的处理方式
+    #[arg(long, value_enum)]
+    on_conflict: Option<CliConflict>,
+    /// 用输出替换原文件（写回源文件所在目录，PDF输出不适用）
+    #[arg(long)]
+    replace_originals: bool,
+    /// 替换原文件时不备份原文件
+    #[arg(long, requires = "replace_originals")]
+    no_backup: bool,
+}
+
+/// PDF输入相关参数
+#[derive(Debug, Args)]
+struct PdfArgs {
+    /// 要渲染的PDF页面，如 "1-3,7,10-"（默认全部页面）
+    #[arg(long)]
+    pages: Option<String>,
+    /// 打开加密PDF的密码
+    #[arg(long)]
+    password: Option<String>,
+    /// 为指定文件设置密码（文件路径或文件名），可重复
+    #[arg(long, value_name = "FILE=PASSWORD", value_parser = parse_file_password)]
+    password_for: Vec<(String, String)>,
+    /// 灰度渲染PDF页面
+    #[arg(long)]
+    grayscale: bool,
+    /// 页面背景：white、transparent 或 #RRGGBB 颜色
+    #[arg(long, value_parser = parse_background)]
+    background: Option<(PageBackground, [u8; 3])>,
+    /// 不渲染注释
+    #[arg(long)]
+    no_annotations: bool,
+    /// 不渲染表单域
+    #[arg(long)]
+    no_forms: bool,
+    /// 按固定宽度渲染页面（像素），与 --height 同时使用时缩放到宽×高范围内
+    #[arg(long, value_name = "PX", value_parser = clap::value_parser!(u32).range(1..=65_535))]
+    width: Option<u32>,
+    /// 按固定高度渲染页面（像素）
+    #[arg(long, value_name = "PX", value_parser = clap::value_parser!(u32).range(1..=65_535))]
+    height: Option<u32>,
+    /// 同时提取PDF文本：每页一个文件或每个文档一个文件（UTF-8）
+    #[arg(long, value_enum)]
+    extract_text: Option<CliTextOutput>,
+    /// 提取文本时同时输出包含单词边界框的JSON文件
+    #[arg(long, requires = "extract_text")]
+    word_boxes: bool,
+}
+
+/// 解析页面背景：white、transparent 或 #RRGGBB
+fn parse_background(value: &str) -> Result<(PageBackground, [u8; 3]), String> {
+    match value.to_lowercase().as_str() {
+        "white" => Ok((PageBackground::White, [255, 255, 255])),
+        "transparent" => Ok((PageBackground::Transparent, [255, 255, 255])),
+        color => {
+            let hex = color.strip_prefix('#').unwrap_or(color);
+            let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+            match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok((PageBackground::Color, [r, g, b])),
+                _ => Err("应为 white、transparent 或 #RRGGBB 颜色".to_string()),
+            }
+        },
+    }
+}
+
+/// 解析 `文件=密码`，密码中可以包含 `=`
+fn parse_file_password(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((file, password)) if !file.is_empty() => Ok((file.to_string(), password.to_string())),
+        _ => Err("格式应为 文件=密码".to_string()),
+    }
+}
+
+/// 水印相关参数
+#[derive(Debug, Args)]
+struct WatermarkArgs {
+    /// 文字水印内容（设置后启用文字水印）
+    #[arg(long)]
+    text: Option<String>,
+    /// 文字大小
+    #[arg(long)]
+    text_size: Option<u32>,
+    /// 文字透明度 (0.0-1.0)
+    #[arg(long)]
+    text_opacity: Option<f32>,
+    /// 文字位置
+    #[arg(long, value_enum)]
+    text_position: Option<CliPosition>,
+    /// 图片水印路径（设置后启用图片水印）
+    #[arg(long)]
+    image: Option<PathBuf>,
+    /// 图片水印缩放比例
+    #[arg(long)]
+    image_scale: Option<f32>,
+    /// 图片水印透明度 (0.0-1.0)
+    #[arg(long)]
+    image_opacity: Option<f32>,
+    /// 图片水印位置
+    #[arg(long, value_enum)]
+    image_position: Option<CliPosition>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliFormat {
+    Jpeg,
+    Png,
+    PngOriginal,
+    Webp,
+    WebpLossless,
+    Avif,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliConflict {
+    Overwrite,
+    Skip,
+    Rename,
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliOrientation {
+    Auto,
+    Landscape,
+    Portrait,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliTextOutput {
+    Page,
+    Document,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum CliPosition {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    MiddleLeft,
+    MiddleCenter,
+    MiddleRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+impl From<CliFormat> for OutputFormat {
+    fn from(format: CliFormat) -> Self {
+        match format {
+            CliFormat::Jpeg => OutputFormat::Jpeg,
+            CliFormat::Png => OutputFormat::PngCompressed,
+            CliFormat::PngOriginal => OutputFormat::PngOriginal,
+            CliFormat::Webp => OutputFormat::WebPLossy,
+            CliFormat::WebpLossless => OutputFormat::WebPLossless,
+            CliFormat::Avif => OutputFormat::Avif,
+        }
+    }
+}
+
+impl From<CliConflict> for CollisionPolicy {
+    fn from(conflict: CliConflict) -> Self {
+        match conflict {
+            CliConflict::Overwrite => CollisionPolicy::Overwrite,
+            CliConflict::Skip => CollisionPolicy::Skip,
+            CliConflict::Rename => CollisionPolicy::Rename,
+            CliConflict::Fail => CollisionPolicy::Fail,
+        }
+    }
+}
+
+impl From<CliOrientation> for PdfPageOrientation {
+    fn from(orientation: CliOrientation) -> Self {
+        match orientation {
+            CliOrientation::Auto => PdfPageOrientation::Auto,
+            CliOrientation::Landscape => PdfPageOrientation::Landscape,
+            CliOrientation::Portrait => PdfPageOrientation::Portrait,
+        }
+    }
+}
+
+impl From<CliTextOutput> for TextOutput {
+    fn from(output: CliTextOutput) -> Self {
+        match output {
+            CliTextOutput::Page => TextOutput::PerPage,
+            CliTextOutput::Document => TextOutput::PerDocument,
+        }
+    }
+}
+
+impl From<CliPosition> for WatermarkPosition {
+    fn from(position: CliPosition) -> Self {
+        match position {
+            CliPosition::TopLeft => WatermarkPosition::TopLeft,
+            CliPosition::TopCenter => WatermarkPosition::TopCenter,
+            CliPosition::TopRight => WatermarkPosition::TopRight,
+            CliPosition::MiddleLeft => WatermarkPosition::MiddleLeft,
+            CliPosition::MiddleCenter => WatermarkPosition::MiddleCenter,
+            CliPosition::MiddleRight => WatermarkPosition::MiddleRight,
+            CliPosition::BottomLeft => WatermarkPosition::BottomLeft,
+            CliPosition::BottomCenter => WatermarkPosition::BottomCenter,
+            CliPosition::BottomRight => WatermarkPosition::BottomRight,
+        }
+    }
+}
+
+/// 一次命令行调用解析出的完整任务
+#[derive(Debug)]
+struct Job {
+    mode: AppMode,
+    input_path: PathBuf,
+    output_path: PathBuf,
+    config: AppConfig,
+}
+
+/// 判断本次启动是否为命令行调用
+///
+/// 只有第一个参数是子命令或帮助/版本选项时才进入命令行模式，
+/// 双击打开图片、拖放文件到程序上或系统附加的其他参数仍然启动界面。
+pub fn is_cli_invocation() -> bool {
+    starts_cli(std::env::args_os().nth(1))
+}
+
+fn starts_cli(first: Option<OsString>) -> bool {
+    let Some(first) = first.as_deref().and_then(|arg| arg.to_str()) else {
+        return false;
+    };
+    matches!(first, "help" | "-h" | "--help" | "-V" | "--version")
+        || Cli::command().get_subcommands().any(|command| command.get_name() == first)
+}
+
+/// 命令行入口，返回进程退出码
+pub fn run<I, T>(args: I) -> i32
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let cli = match Cli::try_parse_from(args) {
+        Ok(cli) => cli,
+        Err(e) => {
+            // --help / --version 也会走到这里，退出码由clap决定
+            let _ = e.print();
+            return e.exit_code();
+        }
+    };
+
+    let job = match build_job(cli.command) {
+        Ok(job) => job,
+        Err(e) => {
+            eprintln!("❌ {:#}", e);
+            return EXIT_USAGE;
+        }
+    };
+
+    execute(job)
+}
+
+/// 合并配置文件与命令行参数
+fn build_job(command: Command) -> anyhow::Result<Job> {
+    let (mode, common) = match &command {
+        Command::Convert { common, .. } => (AppMode::ImageConverter, common),
+        Command::ToPdf { common, .. } => (AppMode::ImageToPdf, common),
+        Command::PdfToImage { common, .. } => (AppMode::PdfToImage, common),
+        Command::Watermark { common, .. } => (AppMode::PureWatermark, common),
+    };
+
+    let mut config = match &common.config {
+        Some(path) => AppConfig::load_from_file(path)?,
+        None => AppConfig::default(),
+    };
+
+    if !common.input.exists() {
+        anyhow::bail!("输入路径不存在: {}", common.input.display());
+    }
+    let input_path = common.input.clone();
+    let output_path = match &common.output {
+        Some(path) => path.clone(),
+        None if !config.default_output_path.is_empty() => PathBuf::from(&config.default_output_path),
+        None => anyhow::bail!("请使用 --output 指定输出文件夹"),
+    };
+
+    config.default_app_mode = mode;
+    config.default_processing_mode = if input_path.is_dir() {
+        ProcessingMode::Folder
+    } else {
+        ProcessingMode::SingleFile
+    };
+    if common.no_recursive {
+        config.folder_settings.recursive = false;
+    }
+    if let Some(depth) = common.max_depth {
+        config.folder_settings.max_depth = depth as usize;
+    }
+    if common.flat {
+        config.folder_settings.preserve_structure = false;
+    }
+    if !common.include.is_empty() {
+        config.discovery.include = common.include.clone();
+    }
+    if !common.exclude.is_empty() {
+        config.discovery.exclude = common.exclude.clone();
+    }
+    if common.hidden {
+        config.discovery.include_hidden = true;
+    }
+
+    match command {
+        Command::Convert { output, watermark, pdf, resume, jobs, memory_limit, .. } => {
+            apply_output_args(&mut config, output);
+            apply_watermark_args(&mut config, watermark);
+            apply_pdf_args(&mut config, pdf)?;
+            if resume {
+                config.resume_batch = true;
+            }
+            if let Some(jobs) = jobs {
+                config.advanced_settings.max_concurrent_jobs = jobs;
+            }
+            if let Some(limit) = memory_limit {
+                config.advanced_settings.memory_limit_mb = limit;
+            }
+        },
+        Command::ToPdf { name, orientation, quality, .. } => {
+            if let Some(name) = name {
+                config.pdf_settings.default_output_name = name;
+            }
+            if let Some(orientation) = orientation {
+                config.pdf_settings.page_orientation = orientation.into();
+            }
+            if let Some(quality) = quality {
+                config.pdf_settings.image_quality = quality;
+            }
+        },
+        Command::PdfToImage { output, pdf, dpi, extract_images, .. } => {
+            let (pdf_width, pdf_height) = (pdf.width.is_some(), pdf.height.is_some());
+            apply_output_args(&mut config, output);
+            apply_pdf_args(&mut config, pdf)?;
+            if let Some(dpi) = dpi {
+                if pdf_width || pdf_height {
+                    anyhow::bail!("--dpi 不能与 --width/--height 同时使用");
+                }
+                config.pdf_render.size_mode = RenderSize::Dpi;
+                if !(72.0..=600.0).contains(&dpi) {
+                    anyhow::bail!("DPI必须在72到600之间: {}", dpi);
+                }
+                config.advanced_settings.pdf_render_dpi = dpi;
+            }
+            if extract_images {
+                config.pdf_render.output = PdfOutput::EmbeddedImages;
+            }
+        },
+        Command::Watermark { watermark, .. } => {
+            apply_watermark_args(&mut config, watermark);
+            let settings = &config.watermark_settings;
+            if !settings.enable_text_watermark && !settings.enable_image_watermark {
+                anyhow::bail!("请使用 --text 或 --image 至少启用一种水印");
+            }
+        },
+    }
+
+    Ok(Job { mode, input_path, output_path, config })
+}
+
+fn apply_output_args(config: &mut AppConfig, args: OutputArgs) {
+    if let Some(format) = args.format {
+        config.default_output_format = format.into();
+    }
+    if let Some(target_kb) = args.target_kb {
+        config.default_target_size = target_kb;
+    }
+    if let Some(template) = args.name_template {
+        config.naming.image_template = template;
+    }
+    if let Some(template) = args.page_template {
+        config.naming.pdf_page_template = template;
+    }
+    if let Some(conflict) = args.on_conflict {
+        config.output_settings.collision = conflict.into();
+    }
+    if args.replace_originals {
+        config.output_settings.replace_originals = true;
+        config.advanced_settings.keep_original_files = !args.no_backup;
+    }
+}
+
+fn apply_pdf_args(config: &mut AppConfig, args: PdfArgs) -> anyhow::Result<()> {
+    if let Some(pages) = args.pages {
+        config.pdf_render.page_range = pages;
+    }
+    if let Some(password) = args.password {
+        config.pdf_render.password = password;
+    }
+    config.pdf_render.file_passwords.extend(args.password_for);
+    if args.grayscale {
+        config.pdf_render.grayscale = true;
+    }
+    if let Some((background, color)) = args.background {
+        config.pdf_render.background = background;
+        config.pdf_render.background_color = color;
+    }
+    if args.no_annotations {
+        config.pdf_render.render_annotations = false;
+    }
+    if args.no_forms {
+        config.pdf_render.render_form_fields = false;
+    }
+    let size_mode = match (args.width, args.height) {
+        (Some(_), Some(_)) => Some(RenderSize::Fit),
+        (Some(_), None) => Some(RenderSize::Width),
+        (None, Some(_)) => Some(RenderSize::Height),
+        (None, None) => None,
+    };
+    if let Some(size_mode) = size_mode {
+        config.pdf_render.size_mode = size_mode;
+        config.pdf_render.target_width = args.width.unwrap_or(config.pdf_render.target_width);
+        config.pdf_render.target_height = args.height.unwrap_or(config.pdf_render.target_height);
+    }
+    if let Some(text) = args.extract_text {
+        config.pdf_text.output = text.into();
+    }
+    if args.word_boxes {
+        config.pdf_text.word_boxes = true;
+    }
+    // 提前检查页面范围，避免开始处理后才报错
+    config.pdf_render.pages()?;
+    Ok(())
+}
+
+fn apply_watermark_args(config: &mut AppConfig, args: WatermarkArgs) {
+    let settings = &mut config.watermark_settings;
+    if let Some(text) = args.text {
+        settings.enable_text_watermark = true;
+        settings.text_content = text;
+    }
+    if let Some(size) = args.text_size {
+        settings.text_size = size;
+    }
+    if let Some(opacity) = args.text_opacity {
+        settings.text_opacity = opacity.clamp(0.0, 1.0);
+    }
+    if let Some(position) = args.text_position {
+        settings.text_position = position.into();
+    }
+    if let Some(image) = args.image {
+        settings.enable_image_watermark = true;
+        settings.image_watermark_path = image.to_string_lossy().to_string();
+    }
+    if let Some(scale) = args.image_scale {
+        settings.image_scale = scale;
+    }
+    if let Some(opacity) = args.image_opacity {
+        settings.image_opacity = opacity.clamp(0.0, 1.0);
+    }
+    if let Some(position) = args.image_position {
+        settings.image_position = position.into();
+    }
+}
+
+/// 在当前线程执行任务，进度输出到标准错误，返回退出码
+fn execute(job: Job) -> i32 {
+    if let Err(e) = std::fs::create_dir_all(&job.output_path) {
+        eprintln!("❌ 无法创建输出目录 {}: {}", job.output_path.display(), e);
+        return EXIT_FAILURE;
+    }
+
+    // Ctrl+C 请求取消，已开始的文件处理完后输出部分结果
+    let signal_runtime = match tokio::runtime::Builder::new_multi_thread().worker_threads(1).enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("❌ 无法创建运行时: {}", e);
+            return EXIT_FAILURE;
+        }
+    };
+    let cancel = CancellationToken::new();
+    let ctrl_c_cancel = cancel.clone();
+    signal_runtime.spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            eprintln!("⏹️  正在取消，等待当前文件处理完成...");
+            ctrl_c_cancel.cancel();
+        }
+    });
+
+    let result = run_job(&job, &TerminalProgress, &cancel);
+    signal_runtime.shutdown_background();
+    exit_code_for(&result)
+}
+
+/// 调用任务模式对应的库入口
+fn run_job(job: &Job, progress: &dyn ProgressSink, cancel: &CancellationToken) -> Result<BatchSummary, ConvertError> {
+    let Job { mode, input_path, output_path, config } = job;
+    match mode {
+        AppMode::ImageConverter => {
+            let options = BatchOptions::from_config(input_path.clone(), output_path.clone(), config);
+            BatchProcessor::process_files(&options, progress, cancel)
+        },
+        AppMode::ImageToPdf => match ImageToPdfConverter::convert(input_path, output_path, config, cancel)? {
+            Some((pdf_path, images)) => {
+                println!("📄 已生成 {}", pdf_path.display());
+                Ok(BatchSummary { processed: images, total: images, ..Default::default() })
+            },
+            None => {
+                println!("⏭️  输出PDF已存在，已跳过");
+                Ok(BatchSummary::default())
+            },
+        },
+        AppMode::PdfToImage => PdfToImageConverter::process_files(input_path, output_path, config, progress, cancel),
+        AppMode::PureWatermark => PureWatermarkProcessor::process_files(input_path, output_path, config, progress, cancel),
+    }
+}
+
+/// 把进度事件逐行输出到标准错误，标准输出留给最终统计
+struct TerminalProgress;
+
+impl ProgressSink for TerminalProgress {
+    fn on_event(&self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::Started { total } => eprintln!("开始处理，共 {} 个任务", total),
+            ProgressEvent::FileDone { path, page, processed, failed, total } => {
+                eprintln!("[{}/{}] {}", processed + failed, total, describe_task(&path, page));
+            },
+            ProgressEvent::FileFailed { path, page, error, processed, failed, total } => {
+                eprintln!("[{}/{}] ❌ {}: {}", processed + failed, total, describe_task(&path, page), error);
+            },
+            ProgressEvent::Finished { .. } => {},
+        }
+    }
+}
+
+/// 根据任务结果决定退出码
+fn exit_code_for(result: &Result<BatchSummary, ConvertError>) -> i32 {
+    let summary = match result {
+        Ok(summary) => summary,
+        Err(ConvertError::Cancelled) => {
+            println!("⏹️  已取消！");
+            return EXIT_CANCELLED;
+        },
+        Err(e @ ConvertError::PdfiumUnavailable { .. }) => {
+            eprintln!("❌ {}", e.chain());
+            eprintln!(
+                "请从 https://github.com/bblanchon/pdfium-binaries/releases 下载对应平台的版本，\
+                 将 {} 放到程序所在目录或系统动态库路径中",
+                Pdfium::pdfium_platform_library_name().to_string_lossy()
+            );
+            return EXIT_FAILURE;
+        },
+        Err(e) => {
+            eprintln!("❌ {}", e.chain());
+            return EXIT_FAILURE;
+        },
+    };
+
+    if summary.cancelled {
+        println!("⏹️  已取消！成功: {}, 失败: {}, 总计: {}", summary.processed, summary.failed, summary.total);
+        return EXIT_CANCELLED;
+    }
+
+    println!("✅ 处理完成！成功: {}, 失败: {}, 总计: {}", summary.processed, summary.failed, summary.total);
+    if summary.failed > 0 {
+        EXIT_PARTIAL
+    } else {
+        EXIT_OK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn parse(args: &[&str]) -> Command {
+        Cli::try_parse_from(args).expect("参数应当解析成功").command
+    }
+
+    #[test]
+    fn test_convert_flags_override_config() {
+        let input = std::env::temp_dir();
+        let input = input.to_str().unwrap();
+        let command = parse(&["image_converter", "convert", input, "-o", "out", "-f", "webp", "-t", "200", "--text", "Hi"]);
+        let job = build_job(command).unwrap();
+
+        assert_eq!(job.mode, AppMode::ImageConverter);
+        assert_eq!(job.config.default_processing_mode, ProcessingMode::Folder);
+        assert_eq!(job.config.default_output_format, OutputFormat::WebPLossy);
+        assert_eq!(job.config.default_target_size, 200);
+        assert!(job.config.watermark_settings.enable_text_watermark);
+        assert_eq!(job.config.watermark_settings.text_content, "Hi");
+        assert!(job.config.folder_settings.recursive);
+        assert!(!job.config.resume_batch);
+
+        let command = parse(&[
+            "image_converter", "convert", input, "-o", "out", "--max-depth", "2", "--flat",
+            "--include", "*.jpg", "--include", "*.png", "--exclude", "raw/**",
+        ]);
+        let job = build_job(command).unwrap();
+        assert_eq!(job.config.folder_settings.max_depth, 2);
+        assert!(!job.config.folder_settings.preserve_structure);
+        assert_eq!(job.config.discovery.include, vec!["*.jpg", "*.png"]);
+        assert_eq!(job.config.discovery.exclude, vec!["raw/**"]);
+        assert!(!job.config.discovery.include_hidden);
+
+        let command = parse(&["image_converter", "convert", input, "-o", "out", "--on-conflict", "skip", "--replace-originals", "--no-backup"]);
+        let job = build_job(command).unwrap();
+        assert_eq!(job.config.output_settings.collision, CollisionPolicy::Skip);
+        assert!(job.config.output_settings.replace_originals);
+        assert!(!job.config.advanced_settings.keep_original_files);
+
+        let command = parse(&["image_converter", "convert", input, "-o", "out", "-j", "2", "--memory-limit", "512"]);
+        let job = build_job(command).unwrap();
+        assert_eq!(job.config.advanced_settings.max_concurrent_jobs, 2);
+        assert_eq!(job.config.advanced_settings.memory_limit_mb, 512);
+    }
+
+    #[test]
+    fn test_watermark_requires_a_watermark() {
+        let input = std::env::temp_dir();
+        let command = parse(&["image_converter", "watermark", input.to_str().unwrap(), "-o", "out"]);
+        assert!(build_job(command).is_err());
+    }
+
+    #[test]
+    fn test_pdf_page_range_is_validated() {
+        let input = std::env::temp_dir();
+        let input = input.to_str().unwrap();
+        let command = parse(&["image_converter", "pdf-to-image", input, "-o", "out", "--pages", "1-3,7,10-"]);
+        let job = build_job(command).unwrap();
+        assert_eq!(job.mode, AppMode::PdfToImage);
+        assert_eq!(job.config.pdf_render.page_range, "1-3,7,10-");
+
+        let command = parse(&["image_converter", "pdf-to-image", input, "-o", "out", "--pages", "3-1"]);
+        assert!(build_job(command).is_err());
+
+        let command = parse(&[
+            "image_converter", "pdf-to-image", input, "-o", "out",
+            "--password", "shared", "--password-for", "secret.pdf=a=b",
+        ]);
+        let settings = build_job(command).unwrap().config.pdf_render;
+        assert_eq!(settings.password_for(Path::new("in/secret.pdf")), Some("a=b"));
+        assert_eq!(settings.password_for(Path::new("in/other.pdf")), Some("shared"));
+        assert!(Cli::try_parse_from(["image_converter", "pdf-to-image", input, "-o", "out", "--password-for", "nopassword"]).is_err());
+
+        let command = parse(&["image_converter", "pdf-to-image", input, "-o", "out", "--grayscale", "--background", "#FF8000", "--no-forms"]);
+        let settings = build_job(command).unwrap().config.pdf_render;
+        assert!(settings.grayscale);
+        assert_eq!((settings.background, settings.background_color), (PageBackground::Color, [255, 128, 0]));
+        assert!(settings.render_annotations);
+        assert!(!settings.render_form_fields);
+        assert!(Cli::try_parse_from(["image_converter", "pdf-to-image", input, "-o", "out", "--background", "#12345"]).is_err());
+
+        let command = parse(&["image_converter", "pdf-to-image", input, "-o", "out", "--width", "1200"]);
+        let settings = build_job(command).unwrap().config.pdf_render;
+        assert_eq!((settings.size_mode, settings.target_width), (RenderSize::Width, 1200));
+        let command = parse(&["image_converter", "pdf-to-image", input, "-o", "out", "--width", "800", "--height", "600"]);
+        let settings = build_job(command).unwrap().config.pdf_render;
+        assert_eq!((settings.size_mode, settings.target_width, settings.target_height), (RenderSize::Fit, 800, 600));
+        let command = parse(&["image_converter", "pdf-to-image", input, "-o", "out", "--height", "900", "--dpi", "300"]);
+        assert!(build_job(command).is_err());
+
+        let command = parse(&["image_converter", "pdf-to-image", input, "-o", "out", "--extract-images"]);
+        assert_eq!(build_job(command).unwrap().config.pdf_render.output, PdfOutput::EmbeddedImages);
+
+        let command = parse(&["image_converter", "convert", input, "-o", "out", "--extract-text", "document", "--word-boxes"]);
+        let text = build_job(command).unwrap().config.pdf_text;
+        assert_eq!((text.output, text.word_boxes), (TextOutput::PerDocument, true));
+        assert!(Cli::try_parse_from(["image_converter", "pdf-to-image", input, "-o", "out", "--word-boxes"]).is_err());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(exit_code_for(&Err(ConvertError::NoImages { path: PathBuf::from("in") })), EXIT_FAILURE);
+        assert_eq!(exit_code_for(&Err(ConvertError::Cancelled)), EXIT_CANCELLED);
+        let partial = BatchSummary { processed: 2, failed: 1, total: 3, cancelled: false };
+        assert_eq!(exit_code_for(&Ok(partial)), EXIT_PARTIAL);
+        let cancelled = BatchSummary { processed: 1, failed: 0, total: 3, cancelled: true };
+        assert_eq!(exit_code_for(&Ok(cancelled)), EXIT_CANCELLED);
+        let ok = BatchSummary { processed: 3, failed: 0, total: 3, cancelled: false };
+        assert_eq!(exit_code_for(&Ok(ok)), EXIT_OK);
+        assert_eq!(run(["image_converter", "unknown"]), EXIT_USAGE);
+    }
+
+    #[test]
+    fn test_jobs_run_without_gui() {
+        let dir = std::env::temp_dir().join(format!("cli_jobs_{}", std::process::id()));
+        let input = dir.join("input");
+        std::fs::create_dir_all(&input).unwrap();
+        image::DynamicImage::new_rgb8(32, 32).save(input.join("a.png")).unwrap();
+        let (input, output) = (input.to_str().unwrap(), dir.join("output"));
+
+        let marked = output.join("watermark");
+        assert_eq!(run(["image_converter", "watermark", input, "-o", marked.to_str().unwrap(), "--text", "Hi"]), EXIT_OK);
+        assert!(marked.join("a.png").exists());
+
+        let pdf = output.join("pdf");
+        assert_eq!(run(["image_converter", "to-pdf", input, "-o", pdf.to_str().unwrap()]), EXIT_OK);
+        assert_eq!(std::fs::read_dir(&pdf).unwrap().count(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_only_subcommands_start_cli() {
+        let starts = |arg: &str| starts_cli(Some(OsString::from(arg)));
+        assert!(starts("convert") && starts("pdf-to-image") && starts("--help") && starts("-V") && starts("help"));
+        assert!(!starts("photo.jpg") && !starts("-psn_0_12345"));
+        assert!(!starts_cli(None));
+    }
+}
@@ -0,0 +1,74 @@
+// AVIF编码器 - 基于纯Rust的 ravif/rav1e，无需系统库
+
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::turbo_encoder::{self, EncodedImage, TargetSearch};
+use image::DynamicImage;
+
+/// rav1e 编码速度 (1-10)：数值越大越快，7 在批处理速度和压缩率之间比较均衡
+const AVIF_SPEED: u8 = 7;
+
+/// 以固定质量编码AVIF，带透明通道的图像保留alpha
+pub fn encode_avif(image: &DynamicImage, quality: u8) -> Result<Vec<u8>> {
+    let start = std::time::Instant::now();
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+
+    let encoder = ravif::Encoder::new()
+        .with_quality(quality as f32)
+        .with_alpha_quality(quality as f32)
+        .with_speed(AVIF_SPEED);
+
+    let encoded = if image.color().has_alpha() {
+        let rgba = image.to_rgba8();
+        let pixels: Vec<ravif::RGBA8> = rgba
+            .pixels()
+            .map(|p| ravif::RGBA8::new(p[0], p[1], p[2], p[3]))
+            .collect();
+        encoder.encode_rgba(ravif::Img::new(pixels.as_slice(), width, height))
+    } else {
+        let rgb = image.to_rgb8();
+        let pixels: Vec<ravif::RGB8> = rgb
+            .pixels()
+            .map(|p| ravif::RGB8::new(p[0], p[1], p[2]))
+            .collect();
+        encoder.encode_rgb(ravif::Img::new(pixels.as_slice(), width, height))
+    }
+    .map_err(|e| ConvertError::encode("AVIF", e))?;
+
+    log::debug!("AVIF编码耗时: {:?}, 质量: {}, 大小: {} bytes",
+               start.elapsed(), quality, encoded.avif_file.len());
+
+    Ok(encoded.avif_file)
+}
+
+/// 按目标大小编码AVIF，与JPEG使用相同的质量二分搜索和缩放策略
+pub fn encode_avif_to_target(
+    image: &DynamicImage,
+    target_bytes: usize,
+    search: &TargetSearch,
+) -> Result<EncodedImage> {
+    turbo_encoder::encode_to_target(image, target_bytes, search, "AVIF", encode_avif)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_avif_encoding() {
+        let test_image = DynamicImage::new_rgba8(64, 48);
+        let data = encode_avif(&test_image, 60).unwrap();
+        assert!(data.len() > 50);
+        assert_eq!(&data[4..8], b"ftyp"); // ISOBMFF文件头
+    }
+
+    #[test]
+    fn test_avif_target_search() {
+        let test_image = DynamicImage::ImageRgb8(image::RgbImage::from_fn(96, 64, |x, y| {
+            image::Rgb([(x * 7 % 256) as u8, (y * 13 % 256) as u8, ((x * y) % 256) as u8])
+        }));
+        let target = 2 * 1024;
+        let result = encode_avif_to_target(&test_image, target, &TargetSearch::default()).unwrap();
+        assert!(result.data.len() <= target);
+    }
+}
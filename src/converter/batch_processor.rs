@@ -1,317 +1,410 @@
 // 文件路径: src/converter/batch_processor.rs
 
-use crate::app::ProgressUpdate;
-use crate::converter::{image_converter, pdf_converter};
-use crate::utils::config::{OutputFormat, ProcessingMode, WatermarkSettings};
-use crate::utils::file_utils::get_files_in_directory;
-use anyhow::Result;
+use crate::converter::cancel::CancellationToken;
+use crate::converter::concurrency::{self, MemoryBudget};
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::progress::{ProgressEvent, ProgressSink};
+use crate::converter::{discovery, image_converter, image_loader, pdf_text};
+use crate::converter::discovery::{Accept, Discovery, DiscoverySettings};
+use crate::converter::image_converter::EncodeOptions;
+use crate::converter::journal::{self, Journal};
+use crate::converter::metadata::SourceMetadata;
+use crate::converter::naming::{NameContext, NamingSettings};
+use crate::converter::output_guard::{OutputGuard, OutputSettings};
+use crate::converter::page_range::PageRange;
+use crate::converter::pdf_converter::{PdfFile, PdfRenderSettings};
+use crate::converter::pdf_text::{PdfTextSettings, TextOutput};
+use crate::converter::resize::ResizeSettings;
+use crate::utils::config::{AppConfig, FolderSettings, OutputFormat, ProcessingMode, WatermarkSettings};
 use rayon::prelude::*;
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use tokio::sync::mpsc;
 // use num_cpus; // <--- 注释掉未使用的导入
 
+/// 批处理参数
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub input_path: PathBuf,
+    pub output_dir: PathBuf,
+    pub target_size_kb: u32,
+    pub output_format: OutputFormat,
+    pub mode: ProcessingMode,
+    pub watermark_settings: WatermarkSettings,
+    pub encode_options: EncodeOptions,
+    /// 编码前的尺寸调整（在添加水印之前进行）
+    pub resize: ResizeSettings,
+    /// 输出文件命名模板
+    pub naming: NamingSettings,
+    /// 文件夹模式的遍历深度和输出目录结构
+    pub folder: FolderSettings,
+    /// 文件夹模式的文件过滤规则
+    pub discovery: DiscoverySettings,
+    /// 输出冲突策略和替换原文件模式
+    pub output: OutputSettings,
+    /// 替换原文件时是否先备份原文件
+    pub keep_original_files: bool,
+    /// 按输出目录中的任务日志继续上次的任务，跳过已完成的文件和页面
+    pub resume: bool,
+    /// 同时处理的最大文件数，0 表示使用全部CPU核心
+    pub max_concurrent_jobs: usize,
+    /// 同时解码图片占用的内存上限（MB），0 表示自动
+    pub memory_limit_mb: usize,
+    /// PDF输入的页面范围等渲染设置
+    pub pdf_render: PdfRenderSettings,
+    /// PDF输入的文本提取设置
+    pub pdf_text: PdfTextSettings,
+}
+
+/// 与默认应用配置一致，输入和输出路径为空
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self::from_config(PathBuf::new(), PathBuf::new(), &AppConfig::default())
+    }
+}
+
+impl BatchOptions {
+    /// 从应用配置构建批处理参数
+    pub fn from_config(input_path: PathBuf, output_dir: PathBuf, config: &AppConfig) -> Self {
+        Self {
+            input_path,
+            output_dir,
+            target_size_kb: config.default_target_size,
+            output_format: config.default_output_format,
+            mode: config.default_processing_mode.clone(),
+            watermark_settings: config.watermark_settings.clone(),
+            encode_options: config.encode_options(),
+            resize: config.resize_settings,
+            naming: config.naming.clone(),
+            folder: config.folder_settings,
+            discovery: config.discovery.clone(),
+            output: config.output_settings,
+            keep_original_files: config.advanced_settings.keep_original_files,
+            resume: config.resume_batch,
+            max_concurrent_jobs: config.advanced_settings.max_concurrent_jobs,
+            memory_limit_mb: config.advanced_settings.memory_limit_mb,
+            pdf_render: config.pdf_render.clone(),
+            pdf_text: config.pdf_text,
+        }
+    }
+
+    /// 影响输出结果的设置摘要，写入任务日志；设置改变后上次的记录不再有效
+    fn settings_digest(&self) -> String {
+        journal::settings_hash(&format!(
+            "{:?}",
+            (
+                self.output_format,
+                self.target_size_kb,
+                &self.encode_options,
+                &self.watermark_settings,
+                self.resize,
+                &self.naming,
+                self.folder.preserve_structure,
+                self.pdf_render.output_digest(),
+                self.pdf_text,
+            )
+        ))
+    }
+}
+
+/// 批处理结果统计
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct BatchSummary {
+    pub processed: usize,
+    pub failed: usize,
+    pub total: usize,
+    /// 批处理被取消，未处理的任务既不计入成功也不计入失败
+    pub cancelled: bool,
+}
+
+/// 一次批处理运行期间共享的状态
+struct BatchContext<'a> {
+    options: &'a BatchOptions,
+    progress: &'a dyn ProgressSink,
+    cancel: &'a CancellationToken,
+    guard: OutputGuard,
+    journal: Journal,
+    memory: MemoryBudget,
+    pdf_pages: PageRange,
+    total_tasks: usize,
+    processed_tasks: AtomicUsize,
+    failed_tasks: AtomicUsize,
+}
+
+impl BatchContext<'_> {
+    fn report_done(&self, path: &Path, page: Option<usize>) {
+        let processed = self.processed_tasks.fetch_add(1, Ordering::SeqCst) + 1;
+        self.progress.on_event(ProgressEvent::FileDone {
+            path: path.to_path_buf(),
+            page,
+            processed,
+            failed: self.failed_tasks.load(Ordering::SeqCst),
+            total: self.total_tasks,
+        });
+    }
+
+    fn report_failed(&self, path: &Path, page: Option<usize>, tasks: usize, error: &ConvertError) {
+        let failed = self.failed_tasks.fetch_add(tasks, Ordering::SeqCst) + tasks;
+        self.progress.on_event(ProgressEvent::FileFailed {
+            path: path.to_path_buf(),
+            page,
+            error: error.chain(),
+            processed: self.processed_tasks.load(Ordering::SeqCst),
+            failed,
+            total: self.total_tasks,
+        });
+    }
+}
+
 pub struct BatchProcessor;
 
 impl BatchProcessor {
-    pub async fn process_files(
-        input_path: PathBuf,
-        output_dir: PathBuf,
-        target_size_kb: u32,
-        output_format: OutputFormat,
-        mode: ProcessingMode,
-        watermark_settings: WatermarkSettings,
-        progress_sender: mpsc::UnboundedSender<ProgressUpdate>,
-    ) {
-        tokio::task::spawn_blocking(move || {
-            let result = Self::run_conversion(
-                input_path,
-                output_dir,
-                target_size_kb,
-                output_format,
-                mode,
-                watermark_settings,
-                &progress_sender,
-            );
-            if let Err(e) = result {
-                let _ = progress_sender.send(ProgressUpdate {
-                    is_complete: true,
-                    error_message: Some(format!("处理失败: {}", e)),
-                    ..Default::default()
-                });
-            }
-        })
-        .await
-        .ok();
-    }
+    /// 同步执行批处理，进度通过 `progress` 报告
+    ///
+    /// 单个文件失败只计入统计；只有无法开始处理（如输入目录不可读）时才返回错误。
+    /// `cancel` 被触发后不再开始新的文件或页面，已完成的结果保留，
+    /// 最终的 `Finished` 事件和返回的统计中 `cancelled` 为 true。
+    pub fn process_files(options: &BatchOptions, progress: &dyn ProgressSink, cancel: &CancellationToken) -> Result<BatchSummary> {
+        let pdf_pages = options.pdf_render.pages()?;
 
-    fn run_conversion(
-        input_path: PathBuf,
-        output_dir: PathBuf,
-        target_size_kb: u32,
-        output_format: OutputFormat,
-        mode: ProcessingMode,
-        watermark_settings: WatermarkSettings,
-        progress_sender: &mpsc::UnboundedSender<ProgressUpdate>,
-    ) -> Result<()> {
-        let files_to_process = match mode {
-            ProcessingMode::SingleFile => vec![input_path],
-            ProcessingMode::Folder => get_files_in_directory(&input_path)?,
+        // 文件按路径排序，保证命名模板中的 {index} 在多次运行间保持一致
+        let files_to_process = match options.mode {
+            ProcessingMode::SingleFile => vec![options.input_path.clone()],
+            ProcessingMode::Folder => Discovery::new(&options.discovery, options.folder.walk_depth())?
+                .files(&options.input_path, Accept::ImagesAndPdfs)?,
         };
 
         if files_to_process.is_empty() {
-            let _ = progress_sender.send(ProgressUpdate { is_complete: true, ..Default::default() });
-            return Ok(());
+            progress.on_event(ProgressEvent::Started { total: 0 });
+            progress.on_event(ProgressEvent::Finished { processed: 0, failed: 0, total: 0, cancelled: false });
+            return Ok(BatchSummary::default());
         }
 
-        // --- 1. 预扫描以获取准确的总任务数（即总输出图片数） ---
-        let _ = progress_sender.send(ProgressUpdate {
-            current_file: "正在计算总任务数...".to_string(),
-            ..Default::default()
-        });
-        let total_tasks: usize = files_to_process.iter().map(|path| {
-            if path.extension().map_or(false, |e| e == "pdf") {
-                match pdf_converter::get_pdf_page_count(path) {
-                    Ok(count) => {
-                        println!("📄 PDF文件 {} 有 {} 页", path.display(), count);
-                        count
-                    },
-                    Err(e) => {
-                        eprintln!("⚠️  无法获取PDF页数 {}: {}，默认为1页", path.display(), e);
-                        1
-                    }
-                }
-            } else {
-                1
+        // --- 1. 预扫描以获取每个文件的任务数（即输出图片数） ---
+        // 预扫描时打开的PDF文档在处理时直接使用，每个PDF只加载一次
+        let documents: Vec<Option<Result<PdfFile>>> = files_to_process.iter()
+            .map(|path| discovery::is_pdf(path).then(|| PdfFile::open(path, options.pdf_render.password_for(path))))
+            .collect();
+        let tasks_per_file: Vec<usize> = files_to_process.iter().zip(&documents).map(|(path, document)| {
+            match document {
+                Some(Ok(document)) => {
+                    let count = pdf_pages.count(document.page_count());
+                    println!("📄 PDF文件 {} 选中 {} 页", path.display(), count);
+                    count
+                },
+                Some(Err(e)) => {
+                    eprintln!("⚠️  无法获取PDF页数 {}: {}，默认为1页", path.display(), e);
+                    1
+                },
+                None => 1,
             }
-        }).sum();
-        
-        let _ = progress_sender.send(ProgressUpdate { total: total_tasks, ..Default::default() });
+        }).collect();
+        let total_tasks: usize = tasks_per_file.iter().sum();
+
+        progress.on_event(ProgressEvent::Started { total: total_tasks });
 
         if total_tasks == 0 {
-            let _ = progress_sender.send(ProgressUpdate { is_complete: true, total: 0, ..Default::default() });
-            return Ok(());
+            progress.on_event(ProgressEvent::Finished { processed: 0, failed: 0, total: 0, cancelled: false });
+            return Ok(BatchSummary::default());
         }
 
-        let processed_tasks = AtomicUsize::new(0);
-        let failed_tasks = AtomicUsize::new(0);
-
-
-        // --- 2. 根据输出格式调整并行策略 ---
-        match output_format {
-            OutputFormat::Jpeg => {
-                // JPEG处理相对较快，可以使用全并行
-                files_to_process.par_iter().for_each(|file_path| {
-                    Self::process_single_file(
-                        file_path,
-                        &output_dir,
-                        target_size_kb,
-                        output_format,
-                        &watermark_settings,
-                        progress_sender,
-                        total_tasks,
-                        &processed_tasks,
-                        &failed_tasks
-                    );
-                });
-            },
-            OutputFormat::PngCompressed => {
-                // 优化后的PNG压缩可以使用更高的并行度
-                Self::process_files_with_optimized_parallelism(
-                    &files_to_process,
-                    &output_dir,
-                    target_size_kb,
-                    output_format,
-                    &watermark_settings,
-                    progress_sender,
-                    total_tasks,
-                    &processed_tasks,
-                    &failed_tasks,
-                );
-            },
-            OutputFormat::PngOriginal => {
-                // PNG原始处理相对较快，可以使用全并行
-                files_to_process.par_iter().for_each(|file_path| {
-                    Self::process_single_file(
-                        file_path,
-                        &output_dir,
-                        target_size_kb,
-                        output_format,
-                        &watermark_settings,
-                        progress_sender,
-                        total_tasks,
-                        &processed_tasks,
-                        &failed_tasks
-                    );
-                });
-            },
-            OutputFormat::WebPLossy | OutputFormat::WebPLossless => {
-                // WebP处理高效，可以使用全并行
-                files_to_process.par_iter().for_each(|file_path| {
-                    Self::process_single_file(
-                        file_path,
-                        &output_dir,
-                        target_size_kb,
-                        output_format,
-                        &watermark_settings,
-                        progress_sender,
-                        total_tasks,
-                        &processed_tasks,
-                        &failed_tasks
-                    );
-                });
-            }
+        let journal = Journal::open(&options.output_dir, options.settings_digest(), options.resume)?;
+        if journal.completed_count() > 0 {
+            println!("♻️  继续上次的任务，已完成 {} 个任务", journal.completed_count());
         }
 
+        let pool = concurrency::build_pool(options.max_concurrent_jobs)?;
+        let memory = MemoryBudget::from_limit_mb(options.memory_limit_mb);
+        println!(
+            "⚙️  并发数: {}，内存上限: {} MB",
+            pool.current_num_threads(),
+            memory.limit() / (1024 * 1024)
+        );
+
+        let context = BatchContext {
+            options,
+            progress,
+            cancel,
+            guard: OutputGuard::new(options.output, options.keep_original_files, &files_to_process),
+            journal,
+            memory,
+            pdf_pages,
+            total_tasks,
+            processed_tasks: AtomicUsize::new(0),
+            failed_tasks: AtomicUsize::new(0),
+        };
+
+        // --- 2. 在专用线程池中并行处理，所有输出格式使用同一并发上限 ---
+        pool.install(|| {
+            files_to_process.par_iter().zip(documents).zip(&tasks_per_file).enumerate().for_each(
+                |(index, ((file_path, document), &tasks))| {
+                    Self::process_single_file(file_path, index + 1, tasks, document, &context);
+                },
+            );
+        });
+
         // --- 3. 发送最终的完成信号 ---
-        let _ = progress_sender.send(ProgressUpdate {
-            processed: processed_tasks.load(Ordering::SeqCst),
-            failed: failed_tasks.load(Ordering::SeqCst),
+        let summary = BatchSummary {
+            processed: context.processed_tasks.load(Ordering::SeqCst),
+            failed: context.failed_tasks.load(Ordering::SeqCst),
             total: total_tasks,
-            is_complete: true,
-            ..Default::default()
+            cancelled: cancel.is_cancelled(),
+        };
+        progress.on_event(ProgressEvent::Finished {
+            processed: summary.processed,
+            failed: summary.failed,
+            total: summary.total,
+            cancelled: summary.cancelled,
         });
 
-        Ok(())
-    }
-
-    /// PNG优化：使用更激进的并行策略
-    fn process_files_with_optimized_parallelism(
-        files_to_process: &[PathBuf],
-        output_dir: &Path,
-        target_size_kb: u32,
-        format: OutputFormat,
-        watermark_settings: &WatermarkSettings,
-        progress_sender: &mpsc::UnboundedSender<ProgressUpdate>,
-        total_tasks: usize,
-        processed_tasks: &AtomicUsize,
-        failed_tasks: &AtomicUsize,
-    ) {
-        // 使用简单的串行处理，避免多线程竞态条件
-        for file_path in files_to_process.iter() {
-            Self::process_single_file(
-                file_path,
-                output_dir,
-                target_size_kb,
-                format,
-                watermark_settings,
-                progress_sender,
-                total_tasks,
-                processed_tasks,
-                failed_tasks
-            );
-        }
+        Ok(summary)
     }
 
-    /// 处理单个文件（PDF或图片）
+    /// 处理单个文件（PDF或图片），`index` 为文件在本批次中从1开始的序号，
+    /// `tasks_in_file` 为预扫描得到的输出图片数，`document` 为预扫描时打开PDF的结果，图片为 None
+    ///
+    /// 开始前按估算的解码内存预留预算，预算不足时等待其他文件处理完成。
+    /// 已取消时直接返回，剩余文件不计入统计。
     fn process_single_file(
         file_path: &Path,
-        output_dir: &Path,
-        target_size_kb: u32,
-        format: OutputFormat,
-        watermark_settings: &WatermarkSettings,
-        progress_sender: &mpsc::UnboundedSender<ProgressUpdate>,
-        total_tasks: usize,
-        processed_tasks: &AtomicUsize,
-        failed_tasks: &AtomicUsize,
+        index: usize,
+        tasks_in_file: usize,
+        document: Option<Result<PdfFile>>,
+        context: &BatchContext,
     ) {
-        let result = if file_path.extension().map_or(false, |e| e == "pdf") {
-            Self::process_pdf(file_path, output_dir, target_size_kb, format, watermark_settings, progress_sender, total_tasks, processed_tasks, failed_tasks)
+        if context.cancel.is_cancelled() {
+            return;
+        }
+        let estimate = if document.is_some() {
+            concurrency::estimate_pdf_bytes()
         } else {
-            Self::process_image(file_path, output_dir, target_size_kb, format, watermark_settings, progress_sender, total_tasks, processed_tasks, failed_tasks)
+            concurrency::estimate_image_bytes(file_path)
         };
-        
-        if result.is_err() {
-            let tasks_in_file = if file_path.extension().map_or(false, |e| e == "pdf") {
-                pdf_converter::get_pdf_page_count(file_path).unwrap_or(1)
-            } else { 1 };
-            failed_tasks.fetch_add(tasks_in_file, Ordering::SeqCst);
+        let _reservation = context.memory.acquire(estimate);
+        // 等待内存期间可能已被取消
+        if context.cancel.is_cancelled() {
+            return;
+        }
+
+        match document {
+            // PDF的逐页结果在 process_pdf 内部报告，这里只处理整个文件无法打开的情况
+            Some(document) => match document.and_then(|document| Self::process_pdf(&document, index, context)) {
+                // 渲染失败的页面已逐页计入失败
+                Ok(()) | Err(ConvertError::Cancelled | ConvertError::NoRenderablePages { .. }) => {},
+                Err(e) => context.report_failed(file_path, None, tasks_in_file, &e),
+            },
+            None => match Self::process_image(file_path, index, context) {
+                Ok(()) => context.report_done(file_path, None),
+                Err(e) => context.report_failed(file_path, None, 1, &e),
+            },
         }
     }
 
-    fn process_pdf(
-        input_path: &Path,
-        output_dir: &Path,
-        target_size_kb: u32,
-        format: OutputFormat,
-        watermark_settings: &WatermarkSettings,
-        progress_sender: &mpsc::UnboundedSender<ProgressUpdate>,
-        total_tasks: usize,
-        processed_tasks: &AtomicUsize,
-        failed_tasks: &AtomicUsize,
-    ) -> Result<()> {
-        let images = pdf_converter::convert_pdf_to_images(input_path, 150.0)?;
-        let pdf_stem = input_path.file_stem().unwrap().to_string_lossy();
-
-        // PDF页面处理：使用安全的串行处理
-        println!("🚀 开始处理 {} 页面", images.len());
-
-        for (i, image) in images.iter().enumerate() {
-            let output_filename = format!("{}_page_{}.{}", pdf_stem, i + 1, format.extension());
-            let output_path = output_dir.join(output_filename);
-
-            let result = if watermark_settings.enable_text_watermark || watermark_settings.enable_image_watermark {
-                let text_watermark = if watermark_settings.enable_text_watermark {
-                    Some(watermark_settings.to_text_watermark())
-                } else {
-                    None
-                };
-
-                let image_watermark = if watermark_settings.enable_image_watermark && !watermark_settings.image_watermark_path.is_empty() {
-                    Some(watermark_settings.to_image_watermark())
-                } else {
-                    None
-                };
-
-                image_converter::compress_and_save_with_watermark(
-                    image, &output_path, target_size_kb, format,
-                    text_watermark.as_ref(), image_watermark.as_ref()
-                )
-            } else {
-                image_converter::compress_and_save(image, &output_path, target_size_kb, format)
-            };
+    fn process_pdf(document: &PdfFile, index: usize, context: &BatchContext) -> Result<()> {
+        let options = context.options;
+        let input_path = document.path();
 
-            if result.is_ok() {
-                processed_tasks.fetch_add(1, Ordering::SeqCst);
+        // 继续任务时上次已完成的页面直接报告完成，不再渲染；所有页面都已完成的PDF直接跳过
+        let selected = context.pdf_pages.pages(document.page_count());
+        let (completed, pending): (Vec<usize>, Vec<usize>) = selected
+            .iter()
+            .partition(|&&page| context.journal.is_done(input_path, Some(page)));
+        for &page in &completed {
+            context.report_done(input_path, Some(page));
+        }
+        if pending.is_empty() && !completed.is_empty() {
+            println!("⏭️  上次已完成: {}", input_path.display());
+            return Ok(());
+        }
+        if !completed.is_empty() {
+            println!("♻️  {} 跳过上次已完成的 {} 页", input_path.display(), completed.len());
+        }
 
-                // 每处理完10页或最后一页，输出进度
-                if (i + 1) % 10 == 0 || i == images.len() - 1 {
-                    println!("📄 已完成页面 {}/{}", i + 1, images.len());
+        // 逐页渲染并立即编码保存，页面图像处理完即释放
+        let mut page_count = 0;
+        let render_config = options.pdf_render.render_config(150.0);
+        document.render_pages(&render_config, &pending, context.cancel, |page, image| {
+            page_count += 1;
+            let saved = image.and_then(|image| {
+                let name = NameContext::new(input_path, index, options.output_format.extension()).with_page(page);
+                match Self::save_with_watermark(&image, name, context, &SourceMetadata::default())? {
+                    Some(written) => context.journal.record(input_path, Some(page), &written),
+                    None => Ok(()),
+                }
+            });
+            match saved {
+                Ok(()) => {
+                    context.report_done(input_path, Some(page));
+
+                    // 每处理完10页输出一次进度
+                    if page.is_multiple_of(10) {
+                        println!("📄 已完成页面 {}", page);
+                    }
+                },
+                Err(e) => {
+                    eprintln!("❌ 页面 {} 处理失败", page);
+                    context.report_failed(input_path, Some(page), 1, &e);
                 }
-            } else {
-                failed_tasks.fetch_add(1, Ordering::SeqCst);
-                eprintln!("❌ 页面 {} 处理失败", i + 1);
             }
+            Ok(())
+        })?;
+        println!("📄 {} 处理完成，共 {} 页", input_path.display(), page_count);
 
-            // 发送进度更新（串行，安全）
-            let _ = progress_sender.send(ProgressUpdate {
-                processed: processed_tasks.load(Ordering::SeqCst),
-                failed: failed_tasks.load(Ordering::SeqCst),
-                total: total_tasks,
-                current_file: format!("{} (第 {} 页)", input_path.to_string_lossy(), i + 1),
-                ..Default::default()
+        // 文本与页面图片写到同一目录；页面结果已经报告，提取失败只输出警告
+        if options.pdf_text.output != TextOutput::Off {
+            let name = NameContext::new(input_path, index, "txt");
+            let written = Self::output_dir(input_path, context).and_then(|output_dir| {
+                pdf_text::write_text(document, &selected, &options.pdf_text, &options.naming, &name, context.cancel, |file_name| {
+                    context.guard.resolve(input_path, output_dir.join(file_name))
+                })
             });
+            match written {
+                Ok(count) => println!("📝 {} 输出 {} 个文本文件", input_path.display(), count),
+                Err(ConvertError::Cancelled) => return Err(ConvertError::Cancelled),
+                Err(e) => eprintln!("⚠️  文本提取失败: {}", e.chain()),
+            }
         }
         Ok(())
     }
 
-    fn process_image(
-        input_path: &Path,
-        output_dir: &Path,
-        target_size_kb: u32,
-        format: OutputFormat,
-        watermark_settings: &WatermarkSettings,
-        progress_sender: &mpsc::UnboundedSender<ProgressUpdate>,
-        total_tasks: usize,
-        processed_tasks: &AtomicUsize,
-        failed_tasks: &AtomicUsize,
-    ) -> Result<()> {
-        let image = image::open(input_path)?;
-        let output_filename = input_path.file_name().unwrap();
-        let output_path = output_dir.join(output_filename).with_extension(format.extension());
+    /// 处理单张图片；替换原文件模式下写入成功后移除（或备份后移除）原文件
+    fn process_image(input_path: &Path, index: usize, context: &BatchContext) -> Result<()> {
+        let options = context.options;
+        if context.journal.is_done(input_path, None) {
+            println!("⏭️  上次已完成: {}", input_path.display());
+            return Ok(());
+        }
+
+        let (image, source_metadata) = image_loader::load_image_with_metadata(input_path)?;
+        let name = NameContext::new(input_path, index, options.output_format.extension());
+
+        match Self::save_with_watermark(&image, name, context, &source_metadata)? {
+            Some(written) => {
+                context.journal.record(input_path, None, &written)?;
+                context.guard.finish(input_path, &written)
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// 调整尺寸、按需添加水印后压缩保存，源图片元数据按策略写入输出
+    ///
+    /// 输出文件名按命名模板生成，`{width}x{height}` 为缩放后的尺寸。
+    /// 返回实际写入的路径，按冲突策略跳过时返回 None。
+    fn save_with_watermark(
+        image: &image::DynamicImage,
+        name: NameContext,
+        context: &BatchContext,
+        source_metadata: &SourceMetadata,
+    ) -> Result<Option<PathBuf>> {
+        let options = context.options;
+        // 先缩放再加水印，水印大小和边距相对于最终尺寸
+        let resized = options.resize.apply(image)?;
+        let image = resized.as_ref();
+        let watermark_settings = &options.watermark_settings;
 
         // 检查是否需要添加水印
-        if watermark_settings.enable_text_watermark || watermark_settings.enable_image_watermark {
+        let final_image = if watermark_settings.enable_text_watermark || watermark_settings.enable_image_watermark {
             let text_watermark = if watermark_settings.enable_text_watermark {
                 Some(watermark_settings.to_text_watermark())
             } else {
@@ -324,22 +417,137 @@ impl BatchProcessor {
                 None
             };
 
-            image_converter::compress_and_save_with_watermark(
-                &image, &output_path, target_size_kb, format,
-                text_watermark.as_ref(), image_watermark.as_ref()
-            )?;
+            Cow::Owned(image_converter::apply_watermarks(
+                image, options.output_format, text_watermark.as_ref(), image_watermark.as_ref()
+            )?)
         } else {
-            image_converter::compress_and_save(&image, &output_path, target_size_kb, format)?;
+            Cow::Borrowed(image)
+        };
+
+        let source = name.source;
+        let Some(output_path) = Self::output_path(&final_image, name, context)? else {
+            println!("⏭️  输出文件已存在，跳过: {}", source.display());
+            return Ok(None);
+        };
+        image_converter::compress_and_save_with_metadata(
+            &final_image, &output_path, options.target_size_kb, options.output_format,
+            &options.encode_options, source_metadata
+        )?;
+        Ok(Some(output_path))
+    }
+
+    /// 分配输出文件路径
+    ///
+    /// 路径已存在时按冲突策略处理，返回 None 表示跳过。
+    fn output_path(image: &image::DynamicImage, name: NameContext, context: &BatchContext) -> Result<Option<PathBuf>> {
+        let options = context.options;
+        let output_dir = Self::output_dir(name.source, context)?;
+
+        let source = name.source;
+        let name = name.with_dimensions(image.width(), image.height());
+        context.guard.resolve(source, output_dir.join(options.naming.file_name(&name)))
+    }
+
+    /// `source` 的输出目录，不存在时创建
+    ///
+    /// 替换原文件模式下为源文件所在目录；文件夹模式下按设置重建源文件的相对子目录。
+    fn output_dir(source: &Path, context: &BatchContext) -> Result<PathBuf> {
+        let options = context.options;
+        let output_dir = if context.guard.replaces_originals() {
+            source.parent().unwrap_or_else(|| Path::new(".")).to_path_buf()
+        } else {
+            match options.mode {
+                ProcessingMode::SingleFile => options.output_dir.clone(),
+                ProcessingMode::Folder => options.folder.output_dir_for(&options.input_path, source, &options.output_dir),
+            }
+        };
+        std::fs::create_dir_all(&output_dir).map_err(|e| ConvertError::io(&output_dir, e))?;
+        Ok(output_dir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_progress_events_for_single_image() {
+        let dir = std::env::temp_dir().join(format!("batch_progress_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("input.png");
+        image::DynamicImage::new_rgb8(64, 64).save(&input).unwrap();
+
+        let options = BatchOptions {
+            input_path: input.clone(),
+            output_dir: dir.clone(),
+            target_size_kb: 100,
+            output_format: OutputFormat::Jpeg,
+            mode: ProcessingMode::SingleFile,
+            keep_original_files: true,
+            max_concurrent_jobs: 2,
+            ..Default::default()
+        };
+        let events = Mutex::new(Vec::new());
+        let sink = |event: ProgressEvent| events.lock().unwrap().push(event);
+
+        let summary = BatchProcessor::process_files(&options, &sink, &CancellationToken::new()).unwrap();
+        assert_eq!(summary, BatchSummary { processed: 1, failed: 0, total: 1, cancelled: false });
+
+        let events = events.into_inner().unwrap();
+        assert_eq!(events.first(), Some(&ProgressEvent::Started { total: 1 }));
+        assert!(matches!(events[1], ProgressEvent::FileDone { processed: 1, .. }));
+        assert_eq!(events.last(), Some(&ProgressEvent::Finished { processed: 1, failed: 0, total: 1, cancelled: false }));
+        assert!(dir.join("input.jpg").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_folder_mode_mirrors_structure_with_depth_limit() {
+        let dir = std::env::temp_dir().join(format!("batch_folders_{}", std::process::id()));
+        let input = dir.join("input");
+        let output = dir.join("output");
+        for sub in ["a", "b", "c/d"] {
+            std::fs::create_dir_all(input.join(sub)).unwrap();
         }
-        processed_tasks.fetch_add(1, Ordering::SeqCst);
+        for file in ["a/img.png", "b/img.png", "c/d/deep.png"] {
+            image::DynamicImage::new_rgb8(16, 16).save(input.join(file)).unwrap();
+        }
+        // 非图片文件和隐藏文件不计入任务，也不算失败
+        std::fs::write(input.join("a/notes.txt"), b"notes").unwrap();
+        std::fs::write(input.join("b/.DS_Store"), b"\0\0\0\x01Bud1").unwrap();
 
-        let _ = progress_sender.send(ProgressUpdate {
-            processed: processed_tasks.load(Ordering::SeqCst),
-            failed: failed_tasks.load(Ordering::SeqCst),
-            total: total_tasks,
-            current_file: input_path.to_string_lossy().to_string(),
+        let options = BatchOptions {
+            input_path: input.clone(),
+            output_dir: output.clone(),
+            target_size_kb: 100,
+            output_format: OutputFormat::Jpeg,
+            mode: ProcessingMode::Folder,
+            folder: FolderSettings { max_depth: 2, ..Default::default() },
+            keep_original_files: true,
+            max_concurrent_jobs: 2,
             ..Default::default()
-        });
-        Ok(())
+        };
+
+        let summary = BatchProcessor::process_files(&options, &crate::converter::progress::NoProgress, &CancellationToken::new()).unwrap();
+        assert_eq!(summary, BatchSummary { processed: 2, failed: 0, total: 2, cancelled: false });
+        assert!(output.join("a/img.jpg").exists());
+        assert!(output.join("b/img.jpg").exists());
+        assert!(!output.join("c").exists());
+
+        // 继续任务时跳过日志中已完成的文件，不会再生成重命名的副本
+        let resumed = BatchOptions { resume: true, ..options.clone() };
+        let summary = BatchProcessor::process_files(&resumed, &crate::converter::progress::NoProgress, &CancellationToken::new()).unwrap();
+        assert_eq!(summary, BatchSummary { processed: 2, failed: 0, total: 2, cancelled: false });
+        assert!(!output.join("a/img (1).jpg").exists());
+
+        // 已取消的批处理不再开始新文件，统计中只标记取消
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let summary = BatchProcessor::process_files(&options, &crate::converter::progress::NoProgress, &cancel).unwrap();
+        assert_eq!(summary, BatchSummary { processed: 0, failed: 0, total: 2, cancelled: true });
+
+        let _ = std::fs::remove_dir_all(&dir);
     }
-}
\ No newline at end of file
+}
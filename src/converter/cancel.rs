@@ -0,0 +1,54 @@
+// 取消令牌 - 界面线程请求取消，工作线程在文件、页面之间检查
+
+use crate::converter::error::{ConvertError, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 可在线程间共享的取消标志
+///
+/// 克隆得到的令牌共享同一个标志。取消只在任务边界生效，
+/// 正在编码的文件会完成写入，不会留下半个输出文件。
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消，可重复调用
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 已取消时返回 [`ConvertError::Cancelled`]，便于在循环中用 `?` 提前结束
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(ConvertError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_state() {
+        let token = CancellationToken::new();
+        let worker = token.clone();
+        assert!(worker.check().is_ok());
+
+        token.cancel();
+        assert!(worker.is_cancelled());
+        assert!(matches!(worker.check(), Err(ConvertError::Cancelled)));
+    }
+}
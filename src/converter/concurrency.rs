@@ -0,0 +1,142 @@
+// 并发控制 - 批处理专用线程池和解码内存预算
+
+use crate::converter::error::{ConvertError, Result};
+use std::path::Path;
+use std::sync::{Condvar, Mutex};
+
+/// 无法读取图片尺寸时按此估算（约 2000万像素的 RGBA 图像）
+const FALLBACK_IMAGE_BYTES: u64 = 80 * 1024 * 1024;
+
+/// 一个任务处理期间同时存在的图像副本数（解码、缩放、加水印/编码缓冲）
+const COPIES_PER_IMAGE: u64 = 3;
+
+/// 150 DPI 渲染一页A4的RGBA位图大小
+const PDF_PAGE_BYTES: u64 = 1240 * 1754 * 4;
+
+/// 自动内存上限取当前可用内存的比例
+const AUTO_MEMORY_FRACTION: u64 = 2;
+
+/// 有效并发数：0 表示使用全部CPU核心
+pub fn effective_jobs(configured: usize) -> usize {
+    if configured > 0 {
+        configured
+    } else {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+    }
+}
+
+/// 创建批处理专用的 rayon 线程池，不占用全局线程池
+pub fn build_pool(jobs: usize) -> Result<rayon::ThreadPool> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(effective_jobs(jobs))
+        .thread_name(|index| format!("batch-worker-{}", index))
+        .build()
+        .map_err(|source| ConvertError::ThreadPool { source })
+}
+
+/// 解码内存预算
+///
+/// 任务开始前按估算大小预留内存，预算不足时等待其他任务释放，
+/// 避免多个工作线程同时解码大图导致内存耗尽。单个任务超出整个预算时
+/// 只在没有其他任务占用内存时运行，保证总能继续处理。
+#[derive(Debug)]
+pub struct MemoryBudget {
+    limit: u64,
+    used: Mutex<u64>,
+    released: Condvar,
+}
+
+impl MemoryBudget {
+    pub fn new(limit_bytes: u64) -> Self {
+        Self { limit: limit_bytes.max(1), used: Mutex::new(0), released: Condvar::new() }
+    }
+
+    /// 按配置创建预算，`limit_mb` 为 0 时使用当前可用内存的一半
+    pub fn from_limit_mb(limit_mb: usize) -> Self {
+        let limit = if limit_mb > 0 {
+            limit_mb as u64 * 1024 * 1024
+        } else {
+            let mut system = sysinfo::System::new();
+            system.refresh_memory();
+            match system.available_memory() {
+                0 => 2 * 1024 * 1024 * 1024,
+                available => available / AUTO_MEMORY_FRACTION,
+            }
+        };
+        Self::new(limit)
+    }
+
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+
+    /// 预留 `bytes` 字节，预算不足时阻塞等待；返回值被丢弃时自动释放
+    pub fn acquire(&self, bytes: u64) -> MemoryReservation<'_> {
+        let mut used = self.used.lock().unwrap();
+        while *used > 0 && *used + bytes > self.limit {
+            used = self.released.wait(used).unwrap();
+        }
+        *used += bytes;
+        MemoryReservation { budget: self, bytes }
+    }
+}
+
+/// 已预留的内存，离开作用域时归还预算
+#[derive(Debug)]
+pub struct MemoryReservation<'a> {
+    budget: &'a MemoryBudget,
+    bytes: u64,
+}
+
+impl Drop for MemoryReservation<'_> {
+    fn drop(&mut self) {
+        let mut used = self.budget.used.lock().unwrap();
+        *used -= self.bytes;
+        self.budget.released.notify_all();
+    }
+}
+
+/// 估算处理一张图片所需的内存，只读取文件头中的尺寸
+pub fn estimate_image_bytes(path: &Path) -> u64 {
+    match image::image_dimensions(path) {
+        Ok((width, height)) => width as u64 * height as u64 * 4 * COPIES_PER_IMAGE,
+        Err(_) => FALLBACK_IMAGE_BYTES,
+    }
+}
+
+/// 估算渲染并处理一个PDF所需的内存（逐页渲染，同一时间只保存一页）
+pub fn estimate_pdf_bytes() -> u64 {
+    PDF_PAGE_BYTES * COPIES_PER_IMAGE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_memory_budget_limits_concurrent_reservations() {
+        let budget = MemoryBudget::new(100);
+        let in_use = AtomicU64::new(0);
+        let peak = AtomicU64::new(0);
+
+        let pool = build_pool(4).unwrap();
+        pool.scope(|scope| {
+            for bytes in [60, 60, 30, 30, 250] {
+                let (budget, in_use, peak) = (&budget, &in_use, &peak);
+                scope.spawn(move |_| {
+                    let _reservation = budget.acquire(bytes);
+                    let now = in_use.fetch_add(bytes, Ordering::SeqCst) + bytes;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(20));
+                    in_use.fetch_sub(bytes, Ordering::SeqCst);
+                });
+            }
+        });
+
+        // 超出预算的任务单独运行，其余组合不超过上限
+        let peak = peak.load(Ordering::SeqCst);
+        assert!(peak == 250 || peak <= 100, "peak = {}", peak);
+        assert_eq!(*budget.used.lock().unwrap(), 0);
+    }
+}
@@ -0,0 +1,256 @@
+// 输入文件发现 - 按文件内容识别格式，所有处理模式共用同一套过滤规则
+
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::output_guard::BACKUP_DIR_NAME;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use walkdir::{DirEntry, WalkDir};
+
+/// 识别格式时读取的文件头长度，PDF 允许 `%PDF-` 出现在前 1024 字节内
+const SNIFF_LEN: usize = 1024;
+
+/// 按文件头识别出的输入格式
+///
+/// 只包含当前构建能够解码的格式，扩展名与内容不符时以内容为准。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Jpeg,
+    Png,
+    WebP,
+    Bmp,
+    Tiff,
+    Pdf,
+}
+
+impl FileKind {
+    /// 根据文件头的魔数识别格式
+    pub fn sniff(header: &[u8]) -> Option<Self> {
+        let kind = match header {
+            [0xFF, 0xD8, 0xFF, ..] => FileKind::Jpeg,
+            [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => FileKind::Png,
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => FileKind::WebP,
+            [b'B', b'M', ..] => FileKind::Bmp,
+            [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => FileKind::Tiff,
+            _ if header.windows(5).any(|w| w == b"%PDF-") => FileKind::Pdf,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    /// 读取文件头识别格式，无法识别时返回 None
+    pub fn detect(path: &Path) -> Result<Option<Self>> {
+        let file = std::fs::File::open(path).map_err(|e| ConvertError::io(path, e))?;
+        let mut header = Vec::with_capacity(SNIFF_LEN);
+        file.take(SNIFF_LEN as u64)
+            .read_to_end(&mut header)
+            .map_err(|e| ConvertError::io(path, e))?;
+        Ok(Self::sniff(&header))
+    }
+
+    pub fn is_image(self) -> bool {
+        self != FileKind::Pdf
+    }
+}
+
+/// 处理模式需要的输入类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accept {
+    Images,
+    Pdfs,
+    ImagesAndPdfs,
+}
+
+impl Accept {
+    pub fn accepts(self, kind: FileKind) -> bool {
+        match self {
+            Accept::Images => kind.is_image(),
+            Accept::Pdfs => kind == FileKind::Pdf,
+            Accept::ImagesAndPdfs => true,
+        }
+    }
+}
+
+/// 文件夹模式的文件过滤设置
+///
+/// 模式使用 glob 语法（如 `*.jpg`、`raw/**`），同时匹配文件名和相对输入文件夹的路径。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoverySettings {
+    /// 只处理匹配任一模式的文件，为空时不限制
+    pub include: Vec<String>,
+    /// 跳过匹配任一模式的文件，优先于 `include`
+    pub exclude: Vec<String>,
+    /// 是否处理隐藏文件和隐藏文件夹中的文件
+    pub include_hidden: bool,
+}
+
+/// 编译好的文件过滤规则
+#[derive(Debug)]
+pub struct Discovery {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    include_hidden: bool,
+    max_depth: usize,
+}
+
+impl Discovery {
+    /// 编译过滤模式，`max_depth` 为相对输入文件夹的最大遍历深度
+    pub fn new(settings: &DiscoverySettings, max_depth: usize) -> Result<Self> {
+        let include = if settings.include.iter().all(|p| p.trim().is_empty()) {
+            None
+        } else {
+            Some(build_glob_set(&settings.include)?)
+        };
+        Ok(Self {
+            include,
+            exclude: build_glob_set(&settings.exclude)?,
+            include_hidden: settings.include_hidden,
+            max_depth,
+        })
+    }
+
+    /// 列出文件夹中符合过滤规则且内容属于 `accept` 的文件（按路径排序）
+    ///
+    /// 替换原文件模式产生的备份文件夹总是被跳过；无法读取的文件视为不支持。
+    pub fn files(&self, root: &Path, accept: Accept) -> Result<Vec<PathBuf>> {
+        if !root.is_dir() {
+            return Err(ConvertError::InvalidInput { path: root.to_path_buf(), reason: "不是一个目录" });
+        }
+
+        let mut files: Vec<PathBuf> = WalkDir::new(root)
+            .min_depth(1)
+            .max_depth(self.max_depth)
+            .into_iter()
+            .filter_entry(|entry| entry.file_name() != BACKUP_DIR_NAME && (self.include_hidden || !is_hidden(entry)))
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter(|entry| self.matches(root, entry.path()))
+            .filter(|entry| matches!(FileKind::detect(entry.path()), Ok(Some(kind)) if accept.accepts(kind)))
+            .map(|entry| entry.into_path())
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// 检查路径是否通过 include/exclude 规则
+    fn matches(&self, root: &Path, path: &Path) -> bool {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let file_name = Path::new(path.file_name().unwrap_or_default());
+        let is_match = |set: &GlobSet| set.is_match(relative) || set.is_match(file_name);
+
+        if is_match(&self.exclude) {
+            return false;
+        }
+        self.include.as_ref().is_none_or(is_match)
+    }
+}
+
+/// 检查单个输入文件的格式，单文件模式下不应用过滤规则
+pub fn check_file(path: &Path, accept: Accept) -> Result<FileKind> {
+    match FileKind::detect(path)? {
+        Some(kind) if accept.accepts(kind) => Ok(kind),
+        _ => Err(ConvertError::UnsupportedFormat {
+            path: path.to_path_buf(),
+            format: path.extension().unwrap_or_default().to_string_lossy().to_lowercase(),
+        }),
+    }
+}
+
+/// 路径是否为PDF（按内容判断）
+pub fn is_pdf(path: &Path) -> bool {
+    matches!(FileKind::detect(path), Ok(Some(FileKind::Pdf)))
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        let glob = Glob::new(pattern).map_err(|source| ConvertError::InvalidPattern {
+            pattern: pattern.to_string(),
+            source,
+        })?;
+        builder.add(glob);
+    }
+    builder.build().map_err(|source| ConvertError::InvalidPattern {
+        pattern: patterns.join(", "),
+        source,
+    })
+}
+
+/// 以 `.` 开头的文件和文件夹视为隐藏；Windows 下还检查隐藏属性
+fn is_hidden(entry: &DirEntry) -> bool {
+    if entry.depth() == 0 {
+        return false;
+    }
+    if entry.file_name().to_string_lossy().starts_with('.') {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if let Ok(metadata) = entry.metadata() {
+            return metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_magic_bytes() {
+        assert_eq!(FileKind::sniff(&[0xFF, 0xD8, 0xFF, 0xE0]), Some(FileKind::Jpeg));
+        assert_eq!(FileKind::sniff(b"\x89PNG\r\n\x1a\n...."), Some(FileKind::Png));
+        assert_eq!(FileKind::sniff(b"RIFF\0\0\0\0WEBPVP8 "), Some(FileKind::WebP));
+        assert_eq!(FileKind::sniff(b"II*\0"), Some(FileKind::Tiff));
+        assert_eq!(FileKind::sniff(b"\n%PDF-1.7"), Some(FileKind::Pdf));
+        assert_eq!(FileKind::sniff(b"RIFF\0\0\0\0WAVEfmt "), None);
+        assert_eq!(FileKind::sniff(b"hello"), None);
+        assert_eq!(FileKind::sniff(b""), None);
+    }
+
+    #[test]
+    fn test_files_filters_content_globs_and_hidden() {
+        let dir = std::env::temp_dir().join(format!("discovery_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("raw")).unwrap();
+        std::fs::create_dir_all(dir.join(".cache")).unwrap();
+
+        let png = b"\x89PNG\r\n\x1a\n";
+        std::fs::write(dir.join("a.png"), png).unwrap();
+        std::fs::write(dir.join("renamed.dat"), png).unwrap(); // 扩展名不符，按内容识别
+        std::fs::write(dir.join("fake.jpg"), b"not an image").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"text").unwrap();
+        std::fs::write(dir.join(".DS_Store"), png).unwrap();
+        std::fs::write(dir.join(".cache").join("b.png"), png).unwrap();
+        std::fs::write(dir.join("raw").join("c.png"), png).unwrap();
+        std::fs::write(dir.join("doc.pdf"), b"%PDF-1.4").unwrap();
+
+        let all = Discovery::new(&DiscoverySettings::default(), usize::MAX).unwrap();
+        assert_eq!(
+            all.files(&dir, Accept::Images).unwrap(),
+            vec![dir.join("a.png"), dir.join("raw").join("c.png"), dir.join("renamed.dat")]
+        );
+        assert_eq!(all.files(&dir, Accept::Pdfs).unwrap(), vec![dir.join("doc.pdf")]);
+
+        let settings = DiscoverySettings {
+            include: vec!["*.png".to_string()],
+            exclude: vec!["raw/**".to_string()],
+            include_hidden: true,
+        };
+        let filtered = Discovery::new(&settings, usize::MAX).unwrap();
+        assert_eq!(
+            filtered.files(&dir, Accept::Images).unwrap(),
+            vec![dir.join(".cache").join("b.png"), dir.join("a.png")]
+        );
+
+        let invalid = DiscoverySettings { include: vec!["[".to_string()], ..Default::default() };
+        assert!(matches!(Discovery::new(&invalid, 1), Err(ConvertError::InvalidPattern { .. })));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
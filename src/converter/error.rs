@@ -0,0 +1,215 @@
+// 转换库的错误类型 - 调用方可以按变体区分错误原因，而不必匹配错误文本
+
+use pdfium_render::prelude::PdfiumError;
+use std::path::PathBuf;
+
+/// 编码器等外部库返回的错误
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// 转换库统一的结果类型
+pub type Result<T, E = ConvertError> = std::result::Result<T, E>;
+
+/// 转换过程中的错误
+///
+/// 底层原因通过 `source()` 保留；界面层需要完整信息时使用 [`ConvertError::chain`]。
+#[derive(Debug, thiserror::Error)]
+pub enum ConvertError {
+    /// 读写文件或遍历目录失败
+    #[error("文件读写失败: {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// 输入路径不存在或类型不符合要求
+    #[error("无效的输入路径 '{}': {reason}", path.display())]
+    InvalidInput { path: PathBuf, reason: &'static str },
+
+    /// 不支持的文件格式
+    #[error("不支持的格式 '{format}': {}", path.display())]
+    UnsupportedFormat { path: PathBuf, format: String },
+
+    /// 图片无法识别或解码
+    #[error("无法解码图片: {}", path.display())]
+    Decode {
+        path: PathBuf,
+        #[source]
+        source: image::ImageError,
+    },
+
+    /// 编码器返回错误
+    #[error("{format}编码失败")]
+    Encode {
+        format: &'static str,
+        #[source]
+        source: BoxError,
+    },
+
+    /// 最低质量和最小缩放仍无法达到目标大小
+    #[error("目标大小过小，无法将{format}压缩到 {target_kb} KB 以内（最小缩放 {scale:.2}x）")]
+    TargetTooSmall {
+        format: &'static str,
+        target_kb: usize,
+        scale: f32,
+    },
+
+    /// 图像缩放失败
+    #[error("图像缩放失败")]
+    Resize {
+        #[source]
+        source: fast_image_resize::ResizeError,
+    },
+
+    /// 写入元数据时遇到无法解析的编码结果
+    #[error("无法写入{format}元数据: {reason}")]
+    Metadata {
+        format: &'static str,
+        reason: &'static str,
+    },
+
+    /// 找不到或无法加载 PDFium 动态库
+    ///
+    /// 安装说明由界面层按平台给出。
+    #[error("无法加载 PDFium 动态库")]
+    PdfiumUnavailable {
+        #[source]
+        source: PdfiumError,
+    },
+
+    /// PDF文件无法打开
+    #[error("无法加载PDF文件: {}", path.display())]
+    PdfLoad {
+        path: PathBuf,
+        #[source]
+        source: PdfiumError,
+    },
+
+    /// PDF已加密，没有提供密码
+    #[error("PDF文件已加密，需要密码: {}", path.display())]
+    PdfPasswordRequired { path: PathBuf },
+
+    /// 提供的PDF密码不正确
+    #[error("PDF密码不正确: {}", path.display())]
+    PdfPasswordIncorrect { path: PathBuf },
+
+    /// PDF页面渲染失败，`page` 从1开始
+    #[error("PDF文件 '{}' 第 {page} 页渲染失败", path.display())]
+    PdfRender {
+        path: PathBuf,
+        page: usize,
+        #[source]
+        source: PdfiumError,
+    },
+
+    /// 提取PDF页面内容失败，`page` 从1开始
+    #[error("PDF文件 '{}' 第 {page} 页提取失败: {reason}", path.display())]
+    PdfExtract {
+        path: PathBuf,
+        page: usize,
+        reason: &'static str,
+    },
+
+    /// PDF中没有任何页面渲染成功
+    #[error("PDF文件 '{}' 中没有可渲染的页面", path.display())]
+    NoRenderablePages { path: PathBuf },
+
+    /// 文件过滤模式不是有效的 glob
+    #[error("无效的文件过滤模式 '{pattern}'")]
+    InvalidPattern {
+        pattern: String,
+        #[source]
+        source: globset::Error,
+    },
+
+    /// PDF页面范围格式错误
+    #[error("无效的页面范围 '{spec}': {reason}")]
+    InvalidPageRange { spec: String, reason: &'static str },
+
+    /// 文件夹中没有可处理的图片
+    #[error("文件夹中没有找到支持的图片文件: {}", path.display())]
+    NoImages { path: PathBuf },
+
+    /// 文件夹中没有可处理的PDF
+    #[error("文件夹中没有找到PDF文件: {}", path.display())]
+    NoPdfs { path: PathBuf },
+
+    /// 纯水印模式没有启用任何水印
+    #[error("请至少启用一种水印类型")]
+    NoWatermark,
+
+    /// 输出文件已存在且冲突策略为报错
+    #[error("输出文件已存在: {}", path.display())]
+    OutputExists { path: PathBuf },
+
+    /// 输出路径指向本次处理的源文件，拒绝覆盖
+    #[error("输出文件与源文件相同，拒绝覆盖: {}", path.display())]
+    WouldOverwriteSource { path: PathBuf },
+
+    /// 无法创建批处理线程池
+    #[error("无法创建处理线程池")]
+    ThreadPool {
+        #[source]
+        source: rayon::ThreadPoolBuildError,
+    },
+
+    /// 用户取消了正在进行的批处理
+    #[error("操作已取消")]
+    Cancelled,
+
+    /// 生成或保存PDF失败
+    #[error("无法保存PDF文件: {}", path.display())]
+    PdfWrite {
+        path: PathBuf,
+        #[source]
+        source: BoxError,
+    },
+}
+
+impl ConvertError {
+    /// 读写 `path` 时的I/O错误
+    pub fn io(path: impl Into<PathBuf>, source: std::io::Error) -> Self {
+        ConvertError::Io { path: path.into(), source }
+    }
+
+    /// `format` 编码器返回的错误
+    pub fn encode(format: &'static str, source: impl Into<BoxError>) -> Self {
+        ConvertError::Encode { format, source: source.into() }
+    }
+
+    /// 包含所有底层原因的完整描述，格式与 anyhow 的 `{:#}` 一致
+    pub fn chain(&self) -> String {
+        let mut message = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            message.push_str(": ");
+            message.push_str(&cause.to_string());
+            source = cause.source();
+        }
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chain_includes_causes() {
+        let error = ConvertError::io(
+            "missing.png",
+            std::io::Error::new(std::io::ErrorKind::NotFound, "not found"),
+        );
+        assert!(matches!(error, ConvertError::Io { .. }));
+        assert_eq!(error.chain(), "文件读写失败: missing.png: not found");
+
+        let error = ConvertError::TargetTooSmall { format: "JPEG", target_kb: 1, scale: 0.25 };
+        assert_eq!(error.chain(), error.to_string());
+
+        // 库错误只描述原因，不包含界面上的安装说明
+        let error = ConvertError::PdfiumUnavailable {
+            source: PdfiumError::PdfiumLibraryInternalError(pdfium_render::prelude::PdfiumInternalError::Unknown),
+        };
+        assert_eq!(error.to_string(), "无法加载 PDFium 动态库");
+    }
+}
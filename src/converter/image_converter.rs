@@ -1,28 +1,105 @@
-use anyhow::{Context, Result};
 use image::{DynamicImage, ImageFormat};
 use std::io::Cursor;
 use std::path::Path;
 // Re-export OutputFormat for external use
 pub use crate::utils::config::OutputFormat;
-use crate::converter::{turbo_encoder, webp_encoder, simple_watermark};
+use crate::converter::{avif_encoder, turbo_encoder, webp_encoder, simple_watermark};
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::metadata::{self, MetadataPolicy, SourceMetadata};
+use crate::converter::png_quantizer::PngQuantOptions;
+use crate::converter::turbo_encoder::{JpegEncoderSettings, TargetSearch};
+
+/// 编码参数（目标大小搜索等），默认值与界面默认配置一致
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    /// JPEG目标大小搜索参数
+    pub target_search: TargetSearch,
+    /// JPEG编码器设置
+    pub jpeg_encoder: JpegEncoderSettings,
+    /// PNG调色板量化设置
+    pub png_quantization: PngQuantOptions,
+    /// 源图片元数据的保留策略
+    pub metadata_policy: MetadataPolicy,
+}
 
 /// 核心函数：压缩图像并保存到文件
+#[allow(dead_code)]
 pub fn compress_and_save(
     image: &DynamicImage,
     output_path: &Path,
     target_kb: u32,
     output_format: OutputFormat,
 ) -> Result<()> {
-    let target_bytes = target_kb as usize * 1024;
+    compress_and_save_with_options(image, output_path, target_kb, output_format, &EncodeOptions::default())
+}
+
+/// 使用指定编码参数压缩图像并保存到文件
+pub fn compress_and_save_with_options(
+    image: &DynamicImage,
+    output_path: &Path,
+    target_kb: u32,
+    output_format: OutputFormat,
+    options: &EncodeOptions,
+) -> Result<()> {
+    compress_and_save_with_metadata(image, output_path, target_kb, output_format, options, &SourceMetadata::default())
+}
+
+/// 压缩图像，按 `options.metadata_policy` 写入源图片元数据后保存到文件
+pub fn compress_and_save_with_metadata(
+    image: &DynamicImage,
+    output_path: &Path,
+    target_kb: u32,
+    output_format: OutputFormat,
+    options: &EncodeOptions,
+    source_metadata: &SourceMetadata,
+) -> Result<()> {
+    // 元数据在编码后写入，先从目标大小中扣除，保证最终文件不超过目标
+    let output_metadata = source_metadata.filtered(options.metadata_policy);
+    let container = metadata::Container::from_output_format(output_format);
+    let metadata_bytes = match container {
+        Some(container) => metadata::embedded_size(container, &output_metadata)?,
+        None => 0,
+    };
+    let target_bytes = (target_kb as usize * 1024).saturating_sub(metadata_bytes);
+
+    let compressed_data = encode(image, output_path, target_bytes, output_format, options).map_err(|e| match e {
+        // 报告用户设置的目标大小，而不是扣除元数据后的值
+        ConvertError::TargetTooSmall { format, scale, .. } => ConvertError::TargetTooSmall { format, target_kb: target_kb as usize, scale },
+        e => e,
+    })?;
+
+    let compressed_data = match container {
+        Some(container) => metadata::embed(compressed_data, container, &output_metadata)?,
+        None => compressed_data,
+    };
+
+    std::fs::write(output_path, compressed_data)
+        .map_err(|e| ConvertError::io(output_path, e))?;
+
+    Ok(())
+}
 
+/// 按输出格式编码，有目标大小的格式编码结果不超过 `target_bytes`
+fn encode(
+    image: &DynamicImage,
+    output_path: &Path,
+    target_bytes: usize,
+    output_format: OutputFormat,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>> {
     let compressed_data = match output_format {
         OutputFormat::Jpeg => {
-            // 使用涡轮增压JPEG编码器
-            turbo_encode_jpeg_adaptive(image, target_bytes)?
+            // 使用涡轮增压JPEG编码器，二分搜索最接近目标的质量
+            let encoded = turbo_encoder::turbo_encode_jpeg_to_target(image, target_bytes, &options.target_search, &options.jpeg_encoder)?;
+            if encoded.scale < 1.0 {
+                println!("📐 {} 最低质量仍超出目标，已缩放至 {:.0}% (质量 {})",
+                         output_path.display(), encoded.scale * 100.0, encoded.quality);
+            }
+            encoded.data
         },
         OutputFormat::PngCompressed => {
-            // 使用涡轮增压PNG压缩编码器
-            turbo_encoder::turbo_encode_png_compressed(image, target_bytes)?
+            // 先调色板量化，仍超出目标时才缩小尺寸
+            turbo_encoder::turbo_encode_png_compressed(image, target_bytes, &options.png_quantization, &options.target_search)?
         },
         OutputFormat::PngOriginal => {
             // 使用涡轮增压PNG快速编码器
@@ -35,31 +112,18 @@ pub fn compress_and_save(
         OutputFormat::WebPLossless => {
             // 使用WebP无损压缩（比PNG更小）
             webp_encoder::encode_webp_lossless(image)?
+        },
+        OutputFormat::Avif => {
+            // 使用AVIF编码器，与JPEG相同的质量二分搜索
+            let encoded = avif_encoder::encode_avif_to_target(image, target_bytes, &options.target_search)?;
+            if encoded.scale < 1.0 {
+                println!("📐 {} 最低质量仍超出目标，已缩放至 {:.0}% (质量 {})",
+                         output_path.display(), encoded.scale * 100.0, encoded.quality);
+            }
+            encoded.data
         }
     };
-
-    std::fs::write(output_path, compressed_data)
-        .with_context(|| format!("无法写入文件到 '{}'", output_path.display()))?;
-
-    Ok(())
-}
-
-/// 涡轮增压自适应JPEG编码器
-fn turbo_encode_jpeg_adaptive(image: &DynamicImage, target_bytes: usize) -> Result<Vec<u8>> {
-    // 快速预估最佳质量
-    let pixels = image.width() * image.height();
-    let _complexity_factor = if pixels > 1_000_000 { 0.7 } else { 0.8 }; // 大图片通常压缩比更高
-
-    let initial_quality = if target_bytes < 50_000 {
-        40 // 小目标：低质量
-    } else if target_bytes < 200_000 {
-        60 // 中目标：中等质量
-    } else {
-        80 // 大目标：高质量
-    };
-
-    // 使用涡轮增压编码器，允许自动调整质量
-    turbo_encoder::turbo_encode_jpeg(image, initial_quality, Some(target_bytes))
+    Ok(compressed_data)
 }
 
 // 已移除 compress_jpeg 函数 - 未使用
@@ -74,11 +138,12 @@ fn compress_png(image: &DynamicImage) -> Result<Vec<u8>> {
     let mut buffer = Cursor::new(Vec::new());
     image
         .write_to(&mut buffer, ImageFormat::Png)
-        .context("编码PNG失败")?;
+        .map_err(|e| ConvertError::encode("PNG", e))?;
     Ok(buffer.into_inner())
 }
 
 /// 添加水印并压缩保存图像
+#[allow(dead_code)]
 pub fn compress_and_save_with_watermark(
     image: &DynamicImage,
     output_path: &Path,
@@ -86,7 +151,21 @@ pub fn compress_and_save_with_watermark(
     output_format: OutputFormat,
     text_watermark: Option<&simple_watermark::SimpleTextWatermark>,
     image_watermark: Option<&simple_watermark::ImageWatermark>,
+    options: &EncodeOptions,
 ) -> Result<()> {
+    let final_image = apply_watermarks(image, output_format, text_watermark, image_watermark)?;
+
+    // 使用原有的压缩保存逻辑
+    compress_and_save_with_options(&final_image, output_path, target_kb, output_format, options)
+}
+
+/// 按需添加文字/图片水印，返回适合目标格式的图像
+pub fn apply_watermarks(
+    image: &DynamicImage,
+    output_format: OutputFormat,
+    text_watermark: Option<&simple_watermark::SimpleTextWatermark>,
+    image_watermark: Option<&simple_watermark::ImageWatermark>,
+) -> Result<DynamicImage> {
     let processor = simple_watermark::SimpleWatermarkProcessor;
     let mut processed_image = image.clone();
 
@@ -112,6 +191,52 @@ pub fn compress_and_save_with_watermark(
         _ => processed_image, // PNG和WebP格式支持RGBA，保持原样
     };
 
-    // 使用原有的压缩保存逻辑
-    compress_and_save(&final_image, output_path, target_kb, output_format)
-}
\ No newline at end of file
+    Ok(final_image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noisy_image(width: u32, height: u32) -> DynamicImage {
+        let mut seed = 7u32;
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(width, height, |x, y| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            image::Rgb([(seed >> 24) as u8, (x % 256) as u8, (y % 256) as u8])
+        }))
+    }
+
+    /// 只有一个 12KB UNDEFINED 条目的小端EXIF，模拟带缩略图的相机EXIF
+    fn large_exif() -> Vec<u8> {
+        let size = 12 * 1024u32;
+        let mut exif = b"II\x2a\0\x08\0\0\0".to_vec();
+        exif.extend_from_slice(&1u16.to_le_bytes());
+        exif.extend_from_slice(&0x927Cu16.to_le_bytes()); // MakerNote
+        exif.extend_from_slice(&7u16.to_le_bytes());
+        exif.extend_from_slice(&size.to_le_bytes());
+        exif.extend_from_slice(&26u32.to_le_bytes());
+        exif.extend_from_slice(&0u32.to_le_bytes());
+        exif.extend((0..size).map(|i| (i * 31 % 251) as u8));
+        exif
+    }
+
+    #[test]
+    fn test_metadata_counts_against_target_size() {
+        let dir = std::env::temp_dir().join(format!("image_converter_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let source = SourceMetadata { exif: Some(large_exif()), icc_profile: Some(vec![3; 4000]), ..Default::default() };
+        let options = EncodeOptions { metadata_policy: MetadataPolicy::KeepAll, ..Default::default() };
+        let target_kb = 40;
+
+        for (format, name) in [(OutputFormat::Jpeg, "out.jpg"), (OutputFormat::PngCompressed, "out.png")] {
+            let output = dir.join(name);
+            compress_and_save_with_metadata(&noisy_image(256, 256), &output, target_kb, format, &options, &source).unwrap();
+            let data = std::fs::read(&output).unwrap();
+            assert!(data.len() <= target_kb as usize * 1024, "{:?}: {} bytes", format, data.len());
+            assert!(data.windows(4).any(|w| w == b"II\x2a\0"), "{:?} 应保留EXIF", format);
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,114 @@
+// 统一图片加载 - 按EXIF方向标签旋转/翻转，保证后续处理看到的是视觉上正确的图像
+
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::metadata::{self, SourceMetadata};
+use image::metadata::Orientation;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use std::path::Path;
+
+/// 加载图片并应用EXIF方向
+///
+/// `image::open` 会忽略方向标签，手机竖拍的照片会横着输出；
+/// 所有需要读取源图片的地方都应使用此函数，水印位置才能落在视觉上正确的角落。
+pub fn load_image(path: &Path) -> Result<DynamicImage> {
+    load_image_with_metadata(path).map(|(image, _)| image)
+}
+
+/// 加载图片并应用EXIF方向，同时读取EXIF、ICC配置文件和XMP供输出时按策略保留
+pub fn load_image_with_metadata(path: &Path) -> Result<(DynamicImage, SourceMetadata)> {
+    let decode_error = |source| ConvertError::Decode { path: path.to_path_buf(), source };
+    let reader = ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| ConvertError::io(path, e))?;
+    let format = reader.format();
+    let mut decoder = reader.into_decoder().map_err(decode_error)?;
+
+    // 元数据读取失败不影响加载；解码器不提供的XMP和PNG的EXIF从文件数据中自行读取
+    let mut source = SourceMetadata {
+        exif: decoder.exif_metadata().ok().flatten().map(strip_exif_prefix),
+        icc_profile: decoder.icc_profile().ok().flatten(),
+        xmp: None,
+    };
+    if matches!(format, Some(ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)) {
+        if let Ok(data) = std::fs::read(path) {
+            source.xmp = metadata::read_xmp(&data);
+            if source.exif.is_none() && format == Some(ImageFormat::Png) {
+                source.exif = metadata::read_png_exif(&data);
+            }
+        }
+    }
+
+    // 方向信息损坏时不影响加载，按原样处理；PNG的方向只能从自行读取的 eXIf 中获得
+    let orientation = match decoder.orientation() {
+        Ok(orientation) if orientation != Orientation::NoTransforms => orientation,
+        _ => source.exif.as_deref()
+            .and_then(metadata::read_orientation)
+            .and_then(Orientation::from_exif)
+            .unwrap_or(Orientation::NoTransforms),
+    };
+
+    let mut image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+    image.apply_orientation(orientation);
+
+    Ok((image, source))
+}
+
+/// 部分WebP写入工具会在EXIF块中保留JPEG的 "Exif\0\0" 前缀
+fn strip_exif_prefix(exif: Vec<u8>) -> Vec<u8> {
+    match exif.strip_prefix(b"Exif\0\0") {
+        Some(tiff) => tiff.to_vec(),
+        None => exif,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在JPEG的SOI之后插入只包含 Orientation 标签的 APP1(Exif) 段
+    fn with_exif_orientation(jpeg: &[u8], orientation: u16) -> Vec<u8> {
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"MM\0\x2a\0\0\0\x08"); // 大端TIFF头，IFD偏移8
+        tiff.extend_from_slice(&1u16.to_be_bytes()); // 1个条目
+        tiff.extend_from_slice(&0x0112u16.to_be_bytes()); // Orientation
+        tiff.extend_from_slice(&3u16.to_be_bytes()); // SHORT
+        tiff.extend_from_slice(&1u32.to_be_bytes());
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0, 0]);
+        tiff.extend_from_slice(&0u32.to_be_bytes()); // 无下一个IFD
+
+        let mut segment = b"Exif\0\0".to_vec();
+        segment.extend_from_slice(&tiff);
+
+        let mut output = jpeg[..2].to_vec();
+        output.extend_from_slice(&[0xFF, 0xE1]);
+        output.extend_from_slice(&((segment.len() + 2) as u16).to_be_bytes());
+        output.extend_from_slice(&segment);
+        output.extend_from_slice(&jpeg[2..]);
+        output
+    }
+
+    #[test]
+    fn test_load_image_applies_exif_orientation() {
+        let dir = std::env::temp_dir().join(format!("image_loader_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut jpeg = Vec::new();
+        DynamicImage::new_rgb8(40, 20)
+            .write_to(&mut std::io::Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+            .unwrap();
+
+        // 6 = 顺时针旋转90度
+        let rotated = dir.join("rotated.jpg");
+        std::fs::write(&rotated, with_exif_orientation(&jpeg, 6)).unwrap();
+        let image = load_image(&rotated).unwrap();
+        assert_eq!((image.width(), image.height()), (20, 40));
+
+        let plain = dir.join("plain.jpg");
+        std::fs::write(&plain, &jpeg).unwrap();
+        let image = load_image(&plain).unwrap();
+        assert_eq!((image.width(), image.height()), (40, 20));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
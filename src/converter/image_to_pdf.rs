@@ -1,12 +1,17 @@
 // 图片转PDF功能模块 - 保持原始尺寸和像素质量
 
-use anyhow::{Context, Result};
 use ::image::{DynamicImage, GenericImageView};
 use printpdf::{PdfDocument, PdfDocumentReference, PdfPageIndex, PdfLayerIndex, Mm, Px, ImageXObject, Image, ImageTransform, ColorSpace, ColorBits};
 use std::path::{Path, PathBuf};
+use crate::converter::cancel::CancellationToken;
+use crate::converter::discovery::{self, Accept, Discovery, DiscoverySettings};
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::image_loader;
+use crate::converter::naming::{self, NameContext};
+use crate::converter::output_guard::{OutputGuard, OutputSettings};
+use crate::utils::config::{AppConfig, PdfPageOrientation};
 use std::fs::File;
 use std::io::BufWriter;
-use walkdir::WalkDir;
 
 /// PDF转换配置 - 升级版
 #[derive(Debug, Clone)]
@@ -29,6 +34,8 @@ pub struct PdfConfig {
     pub auto_rotate: bool,
     /// 页面尺寸模式
     pub page_mode: PageMode,
+    /// 文件夹输入的文件过滤规则（只处理文件夹本身，不进入子文件夹）
+    pub discovery: DiscoverySettings,
 }
 
 /// 页面尺寸模式
@@ -76,6 +83,7 @@ impl Default for PdfConfig {
             margin_mm: 0.0,       // 0mm边距 - 消除白边
             auto_rotate: true,    // 自动旋转
             page_mode: PageMode::AdaptiveSize, // 自适应页面尺寸
+            discovery: DiscoverySettings::default(),
         }
     }
 }
@@ -111,16 +119,69 @@ pub struct ImageToPdfConverter;
 // 删除重复的Default实现，使用上面的新版本
 
 impl ImageToPdfConverter {
+    /// 按应用配置把图片或文件夹中的图片转换为 `output_dir` 中的一个PDF
+    ///
+    /// 输出文件名按PDF命名模板生成，{stem} 为输入文件或文件夹名；已存在时按冲突策略处理。
+    /// 返回PDF路径和图片数量，冲突策略为跳过时返回 None。
+    pub fn convert(
+        input_path: &Path,
+        output_dir: &Path,
+        config: &AppConfig,
+        cancel: &CancellationToken,
+    ) -> Result<Option<(PathBuf, usize)>> {
+        let name = NameContext::new(input_path, 1, "pdf");
+        let mut file_name = naming::render(&config.pdf_settings.default_output_name, &name);
+        if !file_name.to_lowercase().ends_with(".pdf") {
+            file_name.push_str(".pdf");
+        }
+        let collision_only = OutputSettings { replace_originals: false, ..config.output_settings };
+        let guard = OutputGuard::new(collision_only, false, &[input_path.to_path_buf()]);
+        let Some(output_path) = guard.resolve(input_path, output_dir.join(file_name))? else {
+            return Ok(None);
+        };
+
+        let pdf_config = PdfConfig {
+            output_path,
+            preserve_original_size: config.pdf_settings.preserve_original_size,
+            page_orientation: match config.pdf_settings.page_orientation {
+                PdfPageOrientation::Auto => PageOrientation::Auto,
+                PdfPageOrientation::Landscape => PageOrientation::Landscape,
+                PdfPageOrientation::Portrait => PageOrientation::Portrait,
+            },
+            image_quality: config.pdf_settings.image_quality,
+            one_image_per_page: config.pdf_settings.one_image_per_page,
+            // 🚀 新增配置项
+            dpi: 300.0,                           // 高质量300 DPI
+            margin_mm: 0.0,                       // 0mm边距 - 消除白边
+            auto_rotate: true,                    // 启用自动旋转
+            page_mode: PageMode::AdaptiveSize,    // 自适应页面尺寸
+            discovery: config.discovery.clone(),
+        };
+
+        let image_count = match Self::detect_input_type(input_path)? {
+            InputType::SingleImage => {
+                Self::convert_single_image(input_path, &pdf_config, cancel)?;
+                1
+            },
+            InputType::Folder => {
+                let image_count = Self::get_image_files(input_path, &pdf_config)?.len();
+                Self::convert_folder_to_pdf(input_path, &pdf_config, cancel)?;
+                image_count
+            },
+        };
+        Ok(Some((pdf_config.output_path, image_count)))
+    }
+
     /// 将单个图片转换为PDF
     pub fn convert_single_image(
         image_path: &Path,
         config: &PdfConfig,
+        cancel: &CancellationToken,
     ) -> Result<()> {
         println!("🖼️  正在转换: {}", image_path.display());
 
         // 加载图片
-        let image = image::open(image_path)
-            .with_context(|| format!("无法加载图片: {}", image_path.display()))?;
+        let image = image_loader::load_image(image_path)?;
 
         // 创建PDF
         let images = vec![image];
@@ -129,21 +190,24 @@ impl ImageToPdfConverter {
             .to_string_lossy()
             .to_string()];
 
-        Self::create_pdf_from_images(images, image_names, config)
+        Self::create_pdf_from_images(images, image_names, config, cancel)
     }
 
     /// 将文件夹中的所有图片转换为单个PDF
+    ///
+    /// 取消后返回 [`ConvertError::Cancelled`]，不会写出只包含部分页面的PDF。
     pub fn convert_folder_to_pdf(
         folder_path: &Path,
         config: &PdfConfig,
+        cancel: &CancellationToken,
     ) -> Result<()> {
         println!("📁 正在扫描文件夹: {}", folder_path.display());
 
         // 获取所有支持的图片文件
-        let image_files = Self::get_image_files(folder_path)?;
+        let image_files = Self::get_image_files(folder_path, config)?;
 
         if image_files.is_empty() {
-            anyhow::bail!("文件夹中没有找到支持的图片文件");
+            return Err(ConvertError::NoImages { path: folder_path.to_path_buf() });
         }
 
         println!("📸 找到 {} 张图片", image_files.len());
@@ -153,9 +217,10 @@ impl ImageToPdfConverter {
         let mut image_names = Vec::new();
 
         for (i, image_path) in image_files.iter().enumerate() {
+            cancel.check()?;
             println!("📊 加载图片 {}/{}: {}", i + 1, image_files.len(), image_path.display());
 
-            match image::open(image_path) {
+            match image_loader::load_image(image_path) {
                 Ok(img) => {
                     images.push(img);
                     image_names.push(
@@ -166,20 +231,20 @@ impl ImageToPdfConverter {
                     );
                 },
                 Err(e) => {
-                    eprintln!("⚠️  跳过无法加载的图片 {}: {}", image_path.display(), e);
+                    eprintln!("⚠️  跳过无法加载的图片: {}", e.chain());
                     continue;
                 }
             }
         }
 
         if images.is_empty() {
-            anyhow::bail!("没有成功加载任何图片");
+            return Err(ConvertError::NoImages { path: folder_path.to_path_buf() });
         }
 
         println!("✅ 成功加载 {} 张图片", images.len());
 
         // 创建PDF
-        Self::create_pdf_from_images(images, image_names, config)
+        Self::create_pdf_from_images(images, image_names, config, cancel)
     }
 
     /// 从图片列表创建PDF
@@ -187,6 +252,7 @@ impl ImageToPdfConverter {
         images: Vec<DynamicImage>,
         image_names: Vec<String>,
         config: &PdfConfig,
+        cancel: &CancellationToken,
     ) -> Result<()> {
         let start_time = std::time::Instant::now();
         println!("📄 开始创建PDF: {}", config.output_path.display());
@@ -207,6 +273,7 @@ impl ImageToPdfConverter {
 
         // 处理每张图片
         for (i, (image, name)) in images.iter().zip(image_names.iter()).enumerate() {
+            cancel.check()?;
             println!("📝 处理图片 {}/{}: {}", i + 1, images.len(), name);
 
             // 如果不是第一张图片且需要每张图片一页，创建新页面
@@ -219,8 +286,7 @@ impl ImageToPdfConverter {
             }
 
             // 添加图片到PDF
-            Self::add_image_to_pdf(&doc, current_layer, image, config, current_page)
-                .with_context(|| format!("添加图片到PDF失败: {}", name))?;
+            Self::add_image_to_pdf(&doc, current_layer, image, config, current_page)?;
         }
 
         // 保存PDF
@@ -229,15 +295,15 @@ impl ImageToPdfConverter {
         // 确保输出目录存在
         if let Some(parent) = config.output_path.parent() {
             std::fs::create_dir_all(parent)
-                .with_context(|| "创建输出目录失败")?;
+                .map_err(|e| ConvertError::io(parent, e))?;
         }
 
         let file = File::create(&config.output_path)
-            .with_context(|| format!("无法创建PDF文件: {}", config.output_path.display()))?;
+            .map_err(|e| ConvertError::io(&config.output_path, e))?;
         let mut writer = BufWriter::new(file);
 
         doc.save(&mut writer)
-            .with_context(|| "保存PDF文件失败")?;
+            .map_err(|e| ConvertError::PdfWrite { path: config.output_path.clone(), source: e.into() })?;
 
         let elapsed = start_time.elapsed();
         println!("🎉 PDF转换完成!");
@@ -444,59 +510,24 @@ impl ImageToPdfConverter {
 
     // 已移除 calculate_image_position_and_size 函数 - 未使用
 
-    /// 获取文件夹中的所有图片文件（公共接口）
-    pub fn get_image_files_public(folder_path: &Path) -> Result<Vec<PathBuf>> {
-        Self::get_image_files(folder_path)
-    }
-
-    /// 获取文件夹中的所有图片文件
-    fn get_image_files(folder_path: &Path) -> Result<Vec<PathBuf>> {
-        let supported_extensions = ["jpg", "jpeg", "png", "bmp", "tiff", "webp", "gif"];
-        let mut image_files = Vec::new();
-
-        for entry in WalkDir::new(folder_path).min_depth(1).max_depth(1) {
-            let entry = entry.with_context(|| "遍历文件夹失败")?;
-
-            if entry.file_type().is_file() {
-                if let Some(extension) = entry.path().extension() {
-                    let ext_str = extension.to_string_lossy().to_lowercase();
-                    if supported_extensions.contains(&ext_str.as_str()) {
-                        image_files.push(entry.path().to_path_buf());
-                    }
-                }
-            }
-        }
-
-        // 按文件名排序
-        image_files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-
-        Ok(image_files)
+    /// 获取文件夹中的所有图片文件（按内容识别格式，按文件名排序）
+    fn get_image_files(folder_path: &Path, config: &PdfConfig) -> Result<Vec<PathBuf>> {
+        Discovery::new(&config.discovery, 1)?.files(folder_path, Accept::Images)
     }
 
     /// 检查路径是图片文件还是文件夹
     pub fn detect_input_type(path: &Path) -> Result<InputType> {
         if !path.exists() {
-            anyhow::bail!("路径不存在: {}", path.display());
+            return Err(ConvertError::InvalidInput { path: path.to_path_buf(), reason: "路径不存在" });
         }
 
         if path.is_file() {
-            // 检查是否为支持的图片格式
-            if let Some(extension) = path.extension() {
-                let ext_str = extension.to_string_lossy().to_lowercase();
-                let supported_extensions = ["jpg", "jpeg", "png", "bmp", "tiff", "webp", "gif"];
-
-                if supported_extensions.contains(&ext_str.as_str()) {
-                    return Ok(InputType::SingleImage);
-                } else {
-                    anyhow::bail!("不支持的图片格式: {}", ext_str);
-                }
-            } else {
-                anyhow::bail!("文件没有扩展名");
-            }
+            // 按文件内容检查是否为支持的图片格式
+            discovery::check_file(path, Accept::Images).map(|_| InputType::SingleImage)
         } else if path.is_dir() {
             Ok(InputType::Folder)
         } else {
-            anyhow::bail!("未知的路径类型");
+            Err(ConvertError::InvalidInput { path: path.to_path_buf(), reason: "未知的路径类型" })
         }
     }
 }
@@ -519,8 +550,8 @@ mod tests {
         let image = DynamicImage::new_rgb8(1920, 1080);
         let config = PdfConfig::default();
 
-        let (width, height) = ImageToPdfConverter::calculate_page_size(&image, &config);
-        assert!(width.0 > 0.0 && height.0 > 0.0);
+        let (width, height) = ImageToPdfConverter::calculate_page_size(&image, &config).unwrap();
+        assert!(width > 0.0 && height > 0.0);
     }
 
     #[test]
@@ -529,4 +560,20 @@ mod tests {
         assert_eq!(InputType::SingleImage, InputType::SingleImage);
         assert_eq!(InputType::Folder, InputType::Folder);
     }
+
+    #[test]
+    fn test_cancelled_conversion_writes_no_pdf() {
+        let dir = std::env::temp_dir().join(format!("image_to_pdf_cancel_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        DynamicImage::new_rgb8(32, 32).save(dir.join("a.png")).unwrap();
+        let config = PdfConfig::new(dir.join("out.pdf"));
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = ImageToPdfConverter::convert_folder_to_pdf(&dir, &config, &cancel);
+        assert!(matches!(result, Err(ConvertError::Cancelled)));
+        assert!(!config.output_path.exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,161 @@
+// 任务日志 - 在输出目录记录已完成的任务，中断后可以从上次的位置继续
+
+use crate::converter::error::{ConvertError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 日志文件名，位于输出目录中
+pub const JOURNAL_FILE_NAME: &str = ".image_converter_journal.jsonl";
+
+/// 日志中的一行，对应一个已完成的任务（一张图片或PDF的一页）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct JournalEntry {
+    input: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    page: Option<usize>,
+    output: PathBuf,
+    /// 生成该输出时的设置摘要
+    settings: String,
+}
+
+/// 批处理任务日志
+///
+/// 每完成一个任务立即追加一行JSON，进程崩溃时最多丢失正在写入的那一行。
+#[derive(Debug)]
+pub struct Journal {
+    settings: String,
+    completed: HashSet<(PathBuf, Option<usize>)>,
+    file: Mutex<File>,
+}
+
+impl Journal {
+    /// 打开输出目录中的日志
+    ///
+    /// `resume` 为 true 时读取已有记录，只保留设置摘要相同且输出文件仍然存在的任务；
+    /// 否则清空日志开始新任务。
+    pub fn open(output_dir: &Path, settings: String, resume: bool) -> Result<Self> {
+        std::fs::create_dir_all(output_dir).map_err(|e| ConvertError::io(output_dir, e))?;
+        let path = output_dir.join(JOURNAL_FILE_NAME);
+
+        let contents = if resume { read_existing(&path)? } else { String::new() };
+        let completed = contents
+            .lines()
+            // 损坏的行（如崩溃时写了一半）直接忽略
+            .filter_map(|line| serde_json::from_str::<JournalEntry>(line).ok())
+            .filter(|entry| entry.settings == settings && entry.output.exists())
+            .map(|entry| (entry.input, entry.page))
+            .collect();
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(resume)
+            .write(true)
+            .truncate(!resume)
+            .open(&path)
+            .map_err(|e| ConvertError::io(&path, e))?;
+        // 补上被截断的最后一行的换行，新记录从新行开始
+        if !contents.is_empty() && !contents.ends_with('\n') {
+            file.write_all(b"\n").map_err(|e| ConvertError::io(&path, e))?;
+        }
+
+        Ok(Self { settings, completed, file: Mutex::new(file) })
+    }
+
+    /// 任务是否已在之前的运行中完成
+    pub fn is_done(&self, input: &Path, page: Option<usize>) -> bool {
+        self.completed.contains(&(input.to_path_buf(), page))
+    }
+
+    /// 之前的运行中完成的任务数
+    pub fn completed_count(&self) -> usize {
+        self.completed.len()
+    }
+
+    /// 记录一个刚完成的任务
+    pub fn record(&self, input: &Path, page: Option<usize>, output: &Path) -> Result<()> {
+        let entry = JournalEntry {
+            input: input.to_path_buf(),
+            page,
+            output: output.to_path_buf(),
+            settings: self.settings.clone(),
+        };
+        let mut line = serde_json::to_string(&entry).expect("日志条目总能序列化为JSON");
+        line.push('\n');
+
+        // 整行一次写入，多个工作线程的记录不会交错
+        let mut file = self.file.lock().unwrap();
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.flush())
+            .map_err(|e| ConvertError::io(output, e))
+    }
+}
+
+/// 读取已有日志，文件不存在时视为空
+fn read_existing(path: &Path) -> Result<String> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(ConvertError::io(path, e)),
+    }
+}
+
+/// 计算设置摘要（FNV-1a 64位，十六进制）
+///
+/// 输入为影响输出结果的设置的文本表示，设置改变后旧记录不再视为已完成。
+pub fn settings_hash(settings: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in settings.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_skips_completed_tasks_with_same_settings() {
+        let dir = std::env::temp_dir().join(format!("journal_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let output = dir.join("a.jpg");
+        let settings = settings_hash("jpeg 300KB");
+
+        let journal = Journal::open(&dir, settings.clone(), false).unwrap();
+        std::fs::write(&output, b"jpeg").unwrap();
+        journal.record(Path::new("in/a.png"), None, &output).unwrap();
+        journal.record(Path::new("in/doc.pdf"), Some(2), &dir.join("missing.jpg")).unwrap();
+        drop(journal);
+
+        // 崩溃时写了一半的行被忽略
+        let journal_path = dir.join(JOURNAL_FILE_NAME);
+        let mut contents = std::fs::read_to_string(&journal_path).unwrap();
+        contents.push_str("{\"input\":\"in/b.p");
+        std::fs::write(&journal_path, contents).unwrap();
+
+        let resumed = Journal::open(&dir, settings.clone(), true).unwrap();
+        assert!(resumed.is_done(Path::new("in/a.png"), None));
+        // 输出文件已被删除的任务需要重新处理
+        assert!(!resumed.is_done(Path::new("in/doc.pdf"), Some(2)));
+        assert_eq!(resumed.completed_count(), 1);
+
+        // 续写的记录从新行开始，不会接在损坏的行后面
+        resumed.record(Path::new("in/c.png"), None, &output).unwrap();
+        drop(resumed);
+        assert!(Journal::open(&dir, settings.clone(), true).unwrap().is_done(Path::new("in/c.png"), None));
+
+        let changed = Journal::open(&dir, settings_hash("webp 300KB"), true).unwrap();
+        assert_eq!(changed.completed_count(), 0);
+
+        let fresh = Journal::open(&dir, settings, false).unwrap();
+        assert_eq!(fresh.completed_count(), 0);
+        assert_eq!(std::fs::read_to_string(&journal_path).unwrap(), "");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,952 @@
+// 元数据保留 - 按策略将源图片的EXIF/ICC/XMP写回编码后的文件
+//
+// 编码器只输出像素数据，这里在编码完成后按输出容器插入元数据：
+// JPEG 写 APP1(Exif/XMP)/APP2(ICC_PROFILE) 段，PNG 写 eXIf/iCCP/iTXt 块，WebP 写 VP8X 扩展格式的 EXIF/ICCP/XMP 块。
+
+use crate::utils::config::OutputFormat;
+use crate::converter::error::{ConvertError, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+
+/// 元数据处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum MetadataPolicy {
+    /// 全部移除
+    #[default]
+    StripAll,
+    /// 全部保留（EXIF + ICC + XMP）
+    KeepAll,
+    /// 只保留版权和作者
+    CopyrightOnly,
+    /// 只移除GPS定位信息
+    StripGps,
+}
+
+impl MetadataPolicy {
+    /// 获取所有可用策略
+    pub fn all_policies() -> Vec<(Self, &'static str)> {
+        vec![
+            (MetadataPolicy::StripAll, "全部移除"),
+            (MetadataPolicy::KeepAll, "全部保留"),
+            (MetadataPolicy::CopyrightOnly, "仅版权/作者"),
+            (MetadataPolicy::StripGps, "移除GPS"),
+        ]
+    }
+}
+
+/// 从源图片读取的元数据
+#[derive(Debug, Clone, Default)]
+pub struct SourceMetadata {
+    /// EXIF（TIFF结构，不含 "Exif\0\0" 前缀）
+    pub exif: Option<Vec<u8>>,
+    /// ICC色彩配置文件
+    pub icc_profile: Option<Vec<u8>>,
+    /// XMP数据包（UTF-8 XML）
+    pub xmp: Option<Vec<u8>>,
+}
+
+/// 按策略筛选后要写入输出文件的元数据
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputMetadata {
+    pub exif: Option<Vec<u8>>,
+    pub icc_profile: Option<Vec<u8>>,
+    pub xmp: Option<Vec<u8>>,
+    /// 作者（PNG额外写为 iTXt "Author"）
+    pub artist: Option<String>,
+    /// 版权（PNG额外写为 iTXt "Copyright"）
+    pub copyright: Option<String>,
+}
+
+impl OutputMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.exif.is_none() && self.icc_profile.is_none() && self.xmp.is_none()
+            && self.artist.is_none() && self.copyright.is_none()
+    }
+}
+
+impl SourceMetadata {
+    /// 按策略筛选元数据
+    ///
+    /// 加载时已经按方向标签旋转了像素，保留的EXIF会把 Orientation 重置为1，避免查看器再次旋转。
+    /// 移除GPS时同时删除XMP中的 `exif:GPS*` 属性；EXIF中没有作者或版权时使用XMP的 `dc:creator`/`dc:rights`。
+    pub fn filtered(&self, policy: MetadataPolicy) -> OutputMetadata {
+        match policy {
+            MetadataPolicy::StripAll => OutputMetadata::default(),
+            MetadataPolicy::KeepAll | MetadataPolicy::StripGps => {
+                let exif = self.exif.as_ref().map(|exif| {
+                    let mut exif = exif.clone();
+                    reset_orientation(&mut exif);
+                    if policy == MetadataPolicy::StripGps {
+                        strip_gps(&mut exif);
+                    }
+                    exif
+                });
+                let xmp = self.xmp.as_ref().map(|xmp| match policy {
+                    MetadataPolicy::StripGps => strip_xmp_gps(&String::from_utf8_lossy(xmp)).into_bytes(),
+                    _ => xmp.clone(),
+                });
+                OutputMetadata {
+                    exif,
+                    icc_profile: self.icc_profile.clone(),
+                    xmp,
+                    ..Default::default()
+                }
+            },
+            MetadataPolicy::CopyrightOnly => {
+                let (exif_artist, exif_copyright) = self.exif.as_deref().map(read_copyright).unwrap_or_default();
+                let (xmp_artist, xmp_copyright) = self.xmp.as_deref().map(read_xmp_copyright).unwrap_or_default();
+                let artist = exif_artist.or(xmp_artist);
+                let copyright = exif_copyright.or(xmp_copyright);
+                let found = artist.is_some() || copyright.is_some();
+                let exif = found.then(|| build_copyright_exif(artist.as_deref(), copyright.as_deref()));
+                let xmp = found.then(|| build_copyright_xmp(artist.as_deref(), copyright.as_deref()));
+                OutputMetadata { exif, icc_profile: None, xmp, artist, copyright }
+            },
+        }
+    }
+}
+
+/// 支持写入元数据的输出容器
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Container {
+    Jpeg,
+    Png,
+    WebP,
+}
+
+impl Container {
+    /// 根据输出格式确定容器，AVIF暂不支持写入元数据
+    pub fn from_output_format(format: OutputFormat) -> Option<Self> {
+        match format {
+            OutputFormat::Jpeg => Some(Container::Jpeg),
+            OutputFormat::PngCompressed | OutputFormat::PngOriginal => Some(Container::Png),
+            OutputFormat::WebPLossy | OutputFormat::WebPLossless => Some(Container::WebP),
+            OutputFormat::Avif => None,
+        }
+    }
+
+    /// 根据文件扩展名确定容器
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "jpg" | "jpeg" => Some(Container::Jpeg),
+            "png" => Some(Container::Png),
+            "webp" => Some(Container::WebP),
+            _ => None,
+        }
+    }
+}
+
+/// 将元数据写入编码后的文件数据
+pub fn embed(data: Vec<u8>, container: Container, metadata: &OutputMetadata) -> Result<Vec<u8>> {
+    if metadata.is_empty() {
+        return Ok(data);
+    }
+    match container {
+        Container::Jpeg => embed_jpeg(data, metadata),
+        Container::Png => embed_png(data, metadata),
+        Container::WebP => embed_webp(data, metadata),
+    }
+}
+
+/// 元数据写入后文件增加的字节数，用于从目标大小中预先扣除
+///
+/// WebP 按升级为 VP8X 扩展格式计算，已经是扩展格式时会多算10余字节。
+pub fn embedded_size(container: Container, metadata: &OutputMetadata) -> Result<usize> {
+    if metadata.is_empty() {
+        return Ok(0);
+    }
+    Ok(match container {
+        Container::Jpeg => jpeg_segments(metadata).len(),
+        Container::Png => png_chunks(metadata)?.len(),
+        Container::WebP => [Some(10), metadata.icc_profile.as_ref().map(Vec::len), metadata.exif.as_ref().map(Vec::len), metadata.xmp.as_ref().map(Vec::len)]
+            .into_iter()
+            .flatten()
+            .map(|size| 8 + size + (size & 1))
+            .sum(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// EXIF (TIFF) 处理
+// ---------------------------------------------------------------------------
+
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_ARTIST: u16 = 0x013B;
+const TAG_COPYRIGHT: u16 = 0x8298;
+const TAG_GPS_IFD: u16 = 0x8825;
+const TYPE_ASCII: u16 = 2;
+const TYPE_SHORT: u16 = 3;
+
+/// 只读的TIFF视图
+struct Tiff<'a> {
+    data: &'a [u8],
+    big_endian: bool,
+}
+
+/// IFD条目，`pos` 为条目在数据中的起始位置
+struct IfdEntry {
+    pos: usize,
+    tag: u16,
+    field_type: u16,
+    count: u32,
+}
+
+impl<'a> Tiff<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let big_endian = match data.get(..4)? {
+            b"MM\0\x2a" => true,
+            b"II\x2a\0" => false,
+            _ => return None,
+        };
+        Some(Self { data, big_endian })
+    }
+
+    fn u16_at(&self, pos: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(pos..pos + 2)?.try_into().ok()?;
+        Some(if self.big_endian { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+    }
+
+    fn u32_at(&self, pos: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(pos..pos + 4)?.try_into().ok()?;
+        Some(if self.big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+
+    fn ifd0_offset(&self) -> Option<usize> {
+        self.u32_at(4).map(|offset| offset as usize)
+    }
+
+    fn entries(&self, ifd_offset: usize) -> Option<Vec<IfdEntry>> {
+        let count = self.u16_at(ifd_offset)? as usize;
+        (0..count)
+            .map(|i| {
+                let pos = ifd_offset + 2 + i * 12;
+                Some(IfdEntry {
+                    pos,
+                    tag: self.u16_at(pos)?,
+                    field_type: self.u16_at(pos + 2)?,
+                    count: self.u32_at(pos + 4)?,
+                })
+            })
+            .collect()
+    }
+
+    /// 条目值所在的字节范围（不超过4字节时内联在条目中）
+    fn value_range(&self, entry: &IfdEntry) -> Option<std::ops::Range<usize>> {
+        let unit = match entry.field_type {
+            1 | 2 | 6 | 7 => 1,
+            3 | 8 => 2,
+            4 | 9 | 11 => 4,
+            5 | 10 | 12 => 8,
+            _ => return None,
+        };
+        let size = unit * entry.count as usize;
+        let start = if size <= 4 { entry.pos + 8 } else { self.u32_at(entry.pos + 8)? as usize };
+        (start + size <= self.data.len()).then_some(start..start + size)
+    }
+
+    fn ascii_value(&self, entry: &IfdEntry) -> Option<String> {
+        if entry.field_type != TYPE_ASCII {
+            return None;
+        }
+        let bytes = &self.data[self.value_range(entry)?];
+        let text = String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string();
+        (!text.is_empty()).then_some(text)
+    }
+}
+
+fn write_u16(data: &mut [u8], pos: usize, value: u16, big_endian: bool) {
+    let bytes = if big_endian { value.to_be_bytes() } else { value.to_le_bytes() };
+    data[pos..pos + 2].copy_from_slice(&bytes);
+}
+
+/// 读取 IFD0 中的 Orientation 值 (1-8)
+pub fn read_orientation(exif: &[u8]) -> Option<u8> {
+    let tiff = Tiff::parse(exif)?;
+    let entries = tiff.entries(tiff.ifd0_offset()?)?;
+    let entry = entries.into_iter().find(|e| e.tag == TAG_ORIENTATION && e.field_type == TYPE_SHORT)?;
+    tiff.u16_at(entry.pos + 8).and_then(|value| u8::try_from(value).ok())
+}
+
+/// 将 IFD0 中的 Orientation 重置为1（正常方向）
+fn reset_orientation(exif: &mut [u8]) {
+    let target = Tiff::parse(exif).and_then(|tiff| {
+        let entries = tiff.entries(tiff.ifd0_offset()?)?;
+        let entry = entries.into_iter().find(|e| e.tag == TAG_ORIENTATION && e.field_type == TYPE_SHORT)?;
+        Some((entry.pos + 8, tiff.big_endian))
+    });
+    if let Some((pos, big_endian)) = target {
+        write_u16(exif, pos, 1, big_endian);
+    }
+}
+
+/// 移除GPS信息：清零GPS IFD及其数据，并从 IFD0 中删除指向它的条目
+fn strip_gps(exif: &mut [u8]) {
+    let plan = Tiff::parse(exif).and_then(|tiff| {
+        let ifd0 = tiff.ifd0_offset()?;
+        let entries = tiff.entries(ifd0)?;
+        let index = entries.iter().position(|e| e.tag == TAG_GPS_IFD)?;
+        let gps_offset = tiff.u32_at(entries[index].pos + 8)? as usize;
+
+        // 需要清零的区域：GPS IFD本身和其中超过4字节的值
+        let mut zero_ranges = Vec::new();
+        if let Some(gps_entries) = tiff.entries(gps_offset) {
+            zero_ranges.extend(gps_entries.iter().filter_map(|entry| tiff.value_range(entry)));
+            zero_ranges.push(gps_offset..(gps_offset + 2 + gps_entries.len() * 12 + 4).min(tiff.data.len()));
+        }
+        Some((ifd0, entries.len(), index, zero_ranges, tiff.big_endian))
+    });
+
+    let Some((ifd0, count, index, zero_ranges, big_endian)) = plan else {
+        return;
+    };
+
+    for range in zero_ranges {
+        exif[range].fill(0);
+    }
+
+    // 后续条目和下一个IFD偏移整体前移12字节，空出的尾部清零
+    let entry_start = ifd0 + 2 + index * 12;
+    let block_end = ifd0 + 2 + count * 12 + 4;
+    if block_end > exif.len() {
+        return;
+    }
+    exif.copy_within(entry_start + 12..block_end, entry_start);
+    exif[block_end - 12..block_end].fill(0);
+    write_u16(exif, ifd0, (count - 1) as u16, big_endian);
+}
+
+/// 读取 IFD0 中的作者和版权
+fn read_copyright(exif: &[u8]) -> (Option<String>, Option<String>) {
+    let Some(tiff) = Tiff::parse(exif) else {
+        return (None, None);
+    };
+    let Some(entries) = tiff.ifd0_offset().and_then(|offset| tiff.entries(offset)) else {
+        return (None, None);
+    };
+    let find = |tag: u16| entries.iter().find(|e| e.tag == tag).and_then(|e| tiff.ascii_value(e));
+    (find(TAG_ARTIST), find(TAG_COPYRIGHT))
+}
+
+/// 构造只包含作者和版权的最小EXIF
+fn build_copyright_exif(artist: Option<&str>, copyright: Option<&str>) -> Vec<u8> {
+    let fields: Vec<(u16, Vec<u8>)> = [(TAG_ARTIST, artist), (TAG_COPYRIGHT, copyright)]
+        .into_iter()
+        .filter_map(|(tag, text)| {
+            let mut bytes = text?.as_bytes().to_vec();
+            bytes.push(0);
+            Some((tag, bytes))
+        })
+        .collect();
+
+    let mut ifd = Vec::new();
+    let mut values = Vec::new();
+    let values_offset = 8 + 2 + fields.len() * 12 + 4;
+
+    ifd.extend_from_slice(&(fields.len() as u16).to_be_bytes());
+    for (tag, bytes) in &fields {
+        ifd.extend_from_slice(&tag.to_be_bytes());
+        ifd.extend_from_slice(&TYPE_ASCII.to_be_bytes());
+        ifd.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+        if bytes.len() <= 4 {
+            let mut inline = [0u8; 4];
+            inline[..bytes.len()].copy_from_slice(bytes);
+            ifd.extend_from_slice(&inline);
+        } else {
+            ifd.extend_from_slice(&((values_offset + values.len()) as u32).to_be_bytes());
+            values.extend_from_slice(bytes);
+            if values.len() % 2 == 1 {
+                values.push(0); // 值按字对齐
+            }
+        }
+    }
+    ifd.extend_from_slice(&0u32.to_be_bytes());
+
+    let mut exif = b"MM\0\x2a\0\0\0\x08".to_vec();
+    exif.extend_from_slice(&ifd);
+    exif.extend_from_slice(&values);
+    exif
+}
+
+// ---------------------------------------------------------------------------
+// XMP 处理
+// ---------------------------------------------------------------------------
+
+/// JPEG APP1 段中XMP数据包的前缀
+const XMP_JPEG_MARKER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+/// PNG iTXt 块中XMP数据包的关键字
+const XMP_PNG_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+/// 读取JPEG、PNG或WebP文件中的XMP数据包（image 库不提供XMP）
+pub fn read_xmp(data: &[u8]) -> Option<Vec<u8>> {
+    if data.starts_with(&[0xFF, 0xD8]) {
+        read_jpeg_xmp(data)
+    } else if data.starts_with(PNG_SIGNATURE) {
+        read_png_xmp(data)
+    } else if data.get(..4) == Some(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        read_webp_xmp(data)
+    } else {
+        None
+    }
+}
+
+/// 读取XMP中 `dc:creator` 和 `dc:rights` 的第一个值
+fn read_xmp_copyright(xmp: &[u8]) -> (Option<String>, Option<String>) {
+    let xmp = String::from_utf8_lossy(xmp);
+    (xmp_first_item(&xmp, "dc:creator"), xmp_first_item(&xmp, "dc:rights"))
+}
+
+/// 属性中第一个 `rdf:li` 的文本
+fn xmp_first_item(xmp: &str, property: &str) -> Option<String> {
+    let start = xmp.find(&format!("<{}>", property))?;
+    let end = start + xmp[start..].find(&format!("</{}>", property))?;
+    let body = &xmp[start..end];
+    let item = body.find("<rdf:li")?;
+    let text_start = item + body[item..].find('>')? + 1;
+    let text_end = text_start + body[text_start..].find("</rdf:li>")?;
+    let text = unescape_xml(body[text_start..text_end].trim());
+    (!text.is_empty()).then_some(text)
+}
+
+/// 移除所有 `exif:GPS*` 属性，包括属性写法和元素写法
+fn strip_xmp_gps(xmp: &str) -> String {
+    const GPS: &str = "exif:GPS";
+    let mut output = String::with_capacity(xmp.len());
+    let mut rest = xmp;
+    while let Some(pos) = rest.find(GPS) {
+        let name_end = pos + rest[pos..].find(|c: char| !(c.is_ascii_alphanumeric() || c == ':' || c == '_')).unwrap_or(rest.len() - pos);
+        let name = &rest[pos..name_end];
+        let removed = if rest[..pos].ends_with('<') {
+            // 元素写法：<exif:GPSLatitude>...</exif:GPSLatitude> 或自闭合
+            let close = format!("</{}>", name);
+            let end = match (rest[pos..].find("/>"), rest[pos..].find('>'), rest[pos..].find(&close)) {
+                (Some(self_close), Some(open_end), _) if self_close + 1 == open_end => Some(pos + open_end + 1),
+                (_, _, Some(close_pos)) => Some(pos + close_pos + close.len()),
+                _ => None,
+            };
+            end.map(|end| (pos - 1, end))
+        } else if rest[name_end..].starts_with("=\"") || rest[name_end..].starts_with("='") {
+            // 属性写法：exif:GPSLatitude="..."，连同前面的空白一起删除
+            let quote = &rest[name_end + 1..name_end + 2];
+            let value_end = rest[name_end + 2..].find(quote).map(|end| name_end + 2 + end + 1);
+            let start = rest[..pos].trim_end().len();
+            value_end.map(|end| (start, end))
+        } else {
+            None
+        };
+        match removed {
+            Some((start, end)) => {
+                output.push_str(&rest[..start]);
+                rest = &rest[end..];
+            },
+            None => {
+                output.push_str(&rest[..name_end]);
+                rest = &rest[name_end..];
+            },
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+/// 构造只包含作者和版权的最小XMP数据包
+fn build_copyright_xmp(artist: Option<&str>, copyright: Option<&str>) -> Vec<u8> {
+    let mut properties = String::new();
+    if let Some(artist) = artist {
+        properties.push_str(&format!("<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>", escape_xml(artist)));
+    }
+    if let Some(copyright) = copyright {
+        properties.push_str(&format!(
+            "<dc:rights><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:rights>",
+            escape_xml(copyright)
+        ));
+    }
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>\
+         <x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">\
+         <rdf:Description rdf:about=\"\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">{}</rdf:Description>\
+         </rdf:RDF></x:xmpmeta><?xpacket end=\"w\"?>",
+        properties
+    )
+    .into_bytes()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'").replace("&amp;", "&")
+}
+
+// ---------------------------------------------------------------------------
+// JPEG
+// ---------------------------------------------------------------------------
+
+const JPEG_MAX_SEGMENT: usize = 65533;
+const ICC_MARKER: &[u8] = b"ICC_PROFILE\0";
+
+fn embed_jpeg(data: Vec<u8>, metadata: &OutputMetadata) -> Result<Vec<u8>> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err(ConvertError::Metadata { format: "JPEG", reason: "不是有效的JPEG数据" });
+    }
+
+    // JFIF APP0 必须紧跟在 SOI 之后
+    let mut insert_at = 2;
+    if data.get(2..4) == Some(&[0xFF, 0xE0]) {
+        let length = u16::from_be_bytes([data[4], data[5]]) as usize;
+        insert_at = 4 + length;
+    }
+
+    let segments = jpeg_segments(metadata);
+    let mut output = Vec::with_capacity(data.len() + segments.len());
+    output.extend_from_slice(&data[..insert_at]);
+    output.extend_from_slice(&segments);
+    output.extend_from_slice(&data[insert_at..]);
+    Ok(output)
+}
+
+/// 按 Exif、XMP、ICC 顺序生成要插入的APP段
+fn jpeg_segments(metadata: &OutputMetadata) -> Vec<u8> {
+    let mut segments = Vec::new();
+    if let Some(exif) = &metadata.exif {
+        let mut payload = b"Exif\0\0".to_vec();
+        payload.extend_from_slice(exif);
+        if payload.len() <= JPEG_MAX_SEGMENT {
+            push_jpeg_segment(&mut segments, 0xE1, &payload);
+        } else {
+            log::warn!("EXIF过大 ({} bytes)，无法写入JPEG，已跳过", exif.len());
+        }
+    }
+    if let Some(xmp) = &metadata.xmp {
+        // 超过单个段上限的XMP需要扩展XMP，暂不支持
+        let mut payload = XMP_JPEG_MARKER.to_vec();
+        payload.extend_from_slice(xmp);
+        if payload.len() <= JPEG_MAX_SEGMENT {
+            push_jpeg_segment(&mut segments, 0xE1, &payload);
+        } else {
+            log::warn!("XMP过大 ({} bytes)，无法写入JPEG，已跳过", xmp.len());
+        }
+    }
+    if let Some(icc) = &metadata.icc_profile {
+        // ICC配置文件可能超过单个段的上限，按规范分块写入
+        let chunk_size = JPEG_MAX_SEGMENT - ICC_MARKER.len() - 2;
+        let chunks: Vec<&[u8]> = icc.chunks(chunk_size).collect();
+        if chunks.len() <= 255 {
+            for (i, chunk) in chunks.iter().enumerate() {
+                let mut payload = ICC_MARKER.to_vec();
+                payload.push((i + 1) as u8);
+                payload.push(chunks.len() as u8);
+                payload.extend_from_slice(chunk);
+                push_jpeg_segment(&mut segments, 0xE2, &payload);
+            }
+        }
+    }
+    segments
+}
+
+/// 读取JPEG中 APP1 XMP段的数据包，遇到图像数据(SOS)时停止
+fn read_jpeg_xmp(data: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 2;
+    while data.get(pos) == Some(&0xFF) {
+        let marker = *data.get(pos + 1)?;
+        if marker == 0xDA {
+            return None;
+        }
+        let length = u16::from_be_bytes([*data.get(pos + 2)?, *data.get(pos + 3)?]) as usize;
+        let payload = data.get(pos + 4..pos + 2 + length)?;
+        if marker == 0xE1 {
+            if let Some(xmp) = payload.strip_prefix(XMP_JPEG_MARKER) {
+                return Some(xmp.to_vec());
+            }
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+fn push_jpeg_segment(output: &mut Vec<u8>, marker: u8, payload: &[u8]) {
+    output.extend_from_slice(&[0xFF, marker]);
+    output.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+    output.extend_from_slice(payload);
+}
+
+// ---------------------------------------------------------------------------
+// PNG
+// ---------------------------------------------------------------------------
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// 签名(8) + IHDR块(4长度 + 4类型 + 13数据 + 4校验)
+const PNG_IHDR_END: usize = 8 + 25;
+
+fn embed_png(data: Vec<u8>, metadata: &OutputMetadata) -> Result<Vec<u8>> {
+    if !data.starts_with(PNG_SIGNATURE) || data.get(12..16) != Some(b"IHDR") {
+        return Err(ConvertError::Metadata { format: "PNG", reason: "不是有效的PNG数据" });
+    }
+
+    let chunks = png_chunks(metadata)?;
+    let mut output = Vec::with_capacity(data.len() + chunks.len());
+    output.extend_from_slice(&data[..PNG_IHDR_END]);
+    output.extend_from_slice(&chunks);
+    output.extend_from_slice(&data[PNG_IHDR_END..]);
+    Ok(output)
+}
+
+/// 生成紧跟在IHDR之后插入的元数据块
+fn png_chunks(metadata: &OutputMetadata) -> Result<Vec<u8>> {
+    let mut chunks = Vec::new();
+    if let Some(icc) = &metadata.icc_profile {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(icc).map_err(|e| ConvertError::encode("PNG", e))?;
+        let mut payload = b"ICC Profile\0\0".to_vec(); // 名称 + 压缩方法0
+        payload.extend_from_slice(&encoder.finish().map_err(|e| ConvertError::encode("PNG", e))?);
+        push_png_chunk(&mut chunks, b"iCCP", &payload);
+    }
+    if let Some(exif) = &metadata.exif {
+        push_png_chunk(&mut chunks, b"eXIf", exif);
+    }
+    for (keyword, text) in [("Author", &metadata.artist), ("Copyright", &metadata.copyright)] {
+        if let Some(text) = text {
+            push_png_chunk(&mut chunks, b"iTXt", &png_itxt(keyword.as_bytes(), text.as_bytes()));
+        }
+    }
+    if let Some(xmp) = &metadata.xmp {
+        push_png_chunk(&mut chunks, b"iTXt", &png_itxt(XMP_PNG_KEYWORD, xmp));
+    }
+    Ok(chunks)
+}
+
+/// 读取PNG中的 eXIf 块（image 库的PNG解码器不提供EXIF）
+pub fn read_png_exif(data: &[u8]) -> Option<Vec<u8>> {
+    if !data.starts_with(PNG_SIGNATURE) {
+        return None;
+    }
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let chunk_type = &data[pos + 4..pos + 8];
+        match chunk_type {
+            b"eXIf" => return data.get(pos + 8..pos + 8 + length).map(|d| d.to_vec()),
+            b"IDAT" | b"IEND" => return None,
+            _ => pos += 12 + length,
+        }
+    }
+    None
+}
+
+/// 读取PNG中 iTXt "XML:com.adobe.xmp" 块的XMP数据包
+fn read_png_xmp(data: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = PNG_SIGNATURE.len();
+    while pos + 8 <= data.len() {
+        let length = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let payload = data.get(pos + 8..pos + 8 + length)?;
+        match &data[pos + 4..pos + 8] {
+            b"iTXt" if payload.starts_with(XMP_PNG_KEYWORD) && payload.get(XMP_PNG_KEYWORD.len()) == Some(&0) => {
+                // 关键字\0 + 压缩标志 + 压缩方法 + 语言标签\0 + 翻译关键字\0 + 文本
+                let compressed = *payload.get(XMP_PNG_KEYWORD.len() + 1)? == 1;
+                let mut rest = payload.get(XMP_PNG_KEYWORD.len() + 3..)?;
+                for _ in 0..2 {
+                    rest = &rest[rest.iter().position(|&b| b == 0)? + 1..];
+                }
+                if !compressed {
+                    return Some(rest.to_vec());
+                }
+                let mut xmp = Vec::new();
+                std::io::Read::read_to_end(&mut flate2::read::ZlibDecoder::new(rest), &mut xmp).ok()?;
+                return Some(xmp);
+            },
+            b"IEND" => return None,
+            _ => pos += 12 + length,
+        }
+    }
+    None
+}
+
+/// 未压缩的 iTXt 内容：关键字\0 + 未压缩 + 压缩方法 + 语言标签\0 + 翻译关键字\0 + UTF-8文本
+fn png_itxt(keyword: &[u8], text: &[u8]) -> Vec<u8> {
+    let mut payload = keyword.to_vec();
+    payload.extend_from_slice(&[0, 0, 0, 0, 0]);
+    payload.extend_from_slice(text);
+    payload
+}
+
+fn push_png_chunk(output: &mut Vec<u8>, chunk_type: &[u8; 4], payload: &[u8]) {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(chunk_type);
+    hasher.update(payload);
+
+    output.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    output.extend_from_slice(chunk_type);
+    output.extend_from_slice(payload);
+    output.extend_from_slice(&hasher.finalize().to_be_bytes());
+}
+
+// ---------------------------------------------------------------------------
+// WebP
+// ---------------------------------------------------------------------------
+
+const VP8X_FLAG_ICC: u8 = 0x20;
+const VP8X_FLAG_ALPHA: u8 = 0x10;
+const VP8X_FLAG_EXIF: u8 = 0x08;
+const VP8X_FLAG_XMP: u8 = 0x04;
+
+fn embed_webp(data: Vec<u8>, metadata: &OutputMetadata) -> Result<Vec<u8>> {
+    if data.get(..4) != Some(b"RIFF") || data.get(8..12) != Some(b"WEBP") {
+        return Err(ConvertError::Metadata { format: "WebP", reason: "不是有效的WebP数据" });
+    }
+
+    let chunks = riff_chunks(&data);
+
+    // 简单格式需要升级为 VP8X 扩展格式才能携带元数据
+    let mut vp8x = match chunks.first() {
+        Some((fourcc, payload)) if fourcc == b"VP8X" && payload.len() >= 10 => payload.to_vec(),
+        Some((fourcc, payload)) => {
+            let (width, height, alpha) = webp_canvas(fourcc, payload)
+                .ok_or(ConvertError::Metadata { format: "WebP", reason: "无法解析WebP图像尺寸" })?;
+            let mut header = vec![if alpha { VP8X_FLAG_ALPHA } else { 0 }, 0, 0, 0];
+            header.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+            header.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+            header
+        },
+        None => return Err(ConvertError::Metadata { format: "WebP", reason: "WebP数据中没有图像块" }),
+    };
+
+    let body: Vec<([u8; 4], &[u8])> = chunks
+        .into_iter()
+        .filter(|(fourcc, _)| !matches!(fourcc, b"VP8X" | b"ICCP" | b"EXIF" | b"XMP "))
+        .collect();
+
+    let mut output = b"RIFF\0\0\0\0WEBP".to_vec();
+    if metadata.icc_profile.is_some() {
+        vp8x[0] |= VP8X_FLAG_ICC;
+    }
+    if metadata.exif.is_some() {
+        vp8x[0] |= VP8X_FLAG_EXIF;
+    }
+    if metadata.xmp.is_some() {
+        vp8x[0] |= VP8X_FLAG_XMP;
+    }
+    push_riff_chunk(&mut output, b"VP8X", &vp8x);
+    if let Some(icc) = &metadata.icc_profile {
+        push_riff_chunk(&mut output, b"ICCP", icc);
+    }
+    for (fourcc, payload) in &body {
+        push_riff_chunk(&mut output, fourcc, payload);
+    }
+    if let Some(exif) = &metadata.exif {
+        push_riff_chunk(&mut output, b"EXIF", exif);
+    }
+    if let Some(xmp) = &metadata.xmp {
+        push_riff_chunk(&mut output, b"XMP ", xmp);
+    }
+
+    let riff_size = (output.len() - 8) as u32;
+    output[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Ok(output)
+}
+
+/// 解析WebP文件的顶层块
+fn riff_chunks(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut chunks = Vec::new();
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let fourcc: [u8; 4] = data[pos..pos + 4].try_into().unwrap();
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().unwrap()) as usize;
+        let end = (pos + 8 + size).min(data.len());
+        chunks.push((fourcc, &data[pos + 8..end]));
+        pos = pos + 8 + size + (size & 1);
+    }
+    chunks
+}
+
+/// 读取WebP中 "XMP " 块的XMP数据包
+fn read_webp_xmp(data: &[u8]) -> Option<Vec<u8>> {
+    riff_chunks(data).into_iter().find(|(fourcc, _)| fourcc == b"XMP ").map(|(_, payload)| payload.to_vec())
+}
+
+/// 从 VP8/VP8L 块读取画布尺寸和是否含透明通道
+fn webp_canvas(fourcc: &[u8; 4], payload: &[u8]) -> Option<(u32, u32, bool)> {
+    match fourcc {
+        b"VP8 " => {
+            // 3字节帧标签 + 起始码 9d 01 2a + 14位宽 + 14位高
+            if payload.get(3..6)? != [0x9d, 0x01, 0x2a] {
+                return None;
+            }
+            let width = u16::from_le_bytes([*payload.get(6)?, *payload.get(7)?]) as u32 & 0x3FFF;
+            let height = u16::from_le_bytes([*payload.get(8)?, *payload.get(9)?]) as u32 & 0x3FFF;
+            Some((width, height, false))
+        },
+        b"VP8L" => {
+            // 签名 0x2f + 14位(宽-1) + 14位(高-1) + 1位alpha
+            if *payload.first()? != 0x2f {
+                return None;
+            }
+            let bits = u32::from_le_bytes(payload.get(1..5)?.try_into().ok()?);
+            let width = (bits & 0x3FFF) + 1;
+            let height = ((bits >> 14) & 0x3FFF) + 1;
+            let alpha = (bits >> 28) & 1 == 1;
+            Some((width, height, alpha))
+        },
+        _ => None,
+    }
+}
+
+fn push_riff_chunk(output: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    output.extend_from_slice(fourcc);
+    output.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    output.extend_from_slice(payload);
+    if payload.len() % 2 == 1 {
+        output.push(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, ImageDecoder};
+
+    /// 构造包含 Orientation=6、Artist、Copyright 和 GPS IFD 的小端EXIF
+    fn sample_exif() -> Vec<u8> {
+        let mut exif = b"II\x2a\0\x08\0\0\0".to_vec();
+        // IFD0: 4个条目，从偏移8开始，占 2 + 4*12 + 4 = 54 字节，值区从62开始
+        let entries: [(u16, u16, u32, u32); 4] = [
+            (TAG_ORIENTATION, TYPE_SHORT, 1, 6),
+            (TAG_ARTIST, TYPE_ASCII, 6, 62),     // "Alice\0"
+            (TAG_COPYRIGHT, TYPE_ASCII, 8, 68),  // "(c) Bob\0"
+            (TAG_GPS_IFD, 4, 1, 76),
+        ];
+        exif.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for (tag, field_type, count, value) in entries {
+            exif.extend_from_slice(&tag.to_le_bytes());
+            exif.extend_from_slice(&field_type.to_le_bytes());
+            exif.extend_from_slice(&count.to_le_bytes());
+            exif.extend_from_slice(&value.to_le_bytes());
+        }
+        exif.extend_from_slice(&0u32.to_le_bytes());
+        exif.extend_from_slice(b"Alice\0(c) Bob\0");
+        // GPS IFD: 1个条目 GPSLatitudeRef = "N"
+        exif.extend_from_slice(&1u16.to_le_bytes());
+        exif.extend_from_slice(&1u16.to_le_bytes());
+        exif.extend_from_slice(&TYPE_ASCII.to_le_bytes());
+        exif.extend_from_slice(&2u32.to_le_bytes());
+        exif.extend_from_slice(b"N\0\0\0");
+        exif.extend_from_slice(&0u32.to_le_bytes());
+        exif
+    }
+
+    /// 包含GPS（属性和元素两种写法）、作者和版权的XMP
+    const SAMPLE_XMP: &str = "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF>\
+        <rdf:Description rdf:about=\"\" exif:GPSLatitude=\"31,14.4N\" exif:GPSLongitude=\"121,28.8E\" xmp:Rating=\"5\">\
+        <exif:GPSAltitude>12/1</exif:GPSAltitude>\
+        <dc:creator><rdf:Seq><rdf:li>Carol &amp; Dan</rdf:li></rdf:Seq></dc:creator>\
+        <dc:rights><rdf:Alt><rdf:li xml:lang=\"x-default\">(c) Eve</rdf:li></rdf:Alt></dc:rights>\
+        </rdf:Description></rdf:RDF></x:xmpmeta>";
+
+    fn ifd0_tags(exif: &[u8]) -> Vec<u16> {
+        let tiff = Tiff::parse(exif).unwrap();
+        tiff.entries(tiff.ifd0_offset().unwrap()).unwrap().iter().map(|e| e.tag).collect()
+    }
+
+    #[test]
+    fn test_policies_filter_exif() {
+        let source = SourceMetadata {
+            exif: Some(sample_exif()),
+            icc_profile: Some(vec![1, 2, 3]),
+            xmp: Some(SAMPLE_XMP.as_bytes().to_vec()),
+        };
+
+        assert!(source.filtered(MetadataPolicy::StripAll).is_empty());
+
+        let keep = source.filtered(MetadataPolicy::KeepAll);
+        assert_eq!(read_orientation(&sample_exif()), Some(6));
+        assert_eq!(read_orientation(keep.exif.as_ref().unwrap()), Some(1));
+        assert_eq!(ifd0_tags(keep.exif.as_ref().unwrap()).len(), 4);
+        assert_eq!(keep.icc_profile, Some(vec![1, 2, 3]));
+        assert_eq!(keep.xmp.as_deref(), Some(SAMPLE_XMP.as_bytes()));
+
+        let no_gps = source.filtered(MetadataPolicy::StripGps);
+        let exif = no_gps.exif.unwrap();
+        assert_eq!(ifd0_tags(&exif), vec![TAG_ORIENTATION, TAG_ARTIST, TAG_COPYRIGHT]);
+        assert!(!exif.windows(2).any(|w| w == b"N\0"));
+        let xmp = String::from_utf8(no_gps.xmp.unwrap()).unwrap();
+        assert!(!xmp.contains("GPS"), "{}", xmp);
+        assert!(xmp.contains("<rdf:Description rdf:about=\"\" xmp:Rating=\"5\">"), "{}", xmp);
+        assert!(xmp.contains("<dc:rights>"));
+
+        let copyright = source.filtered(MetadataPolicy::CopyrightOnly);
+        assert_eq!(copyright.artist.as_deref(), Some("Alice"));
+        assert_eq!(copyright.copyright.as_deref(), Some("(c) Bob"));
+        assert_eq!(ifd0_tags(copyright.exif.as_ref().unwrap()), vec![TAG_ARTIST, TAG_COPYRIGHT]);
+        assert_eq!(read_copyright(copyright.exif.as_ref().unwrap()),
+                   (Some("Alice".to_string()), Some("(c) Bob".to_string())));
+        assert!(copyright.icc_profile.is_none());
+        assert_eq!(read_xmp_copyright(copyright.xmp.as_ref().unwrap()),
+                   (Some("Alice".to_string()), Some("(c) Bob".to_string())));
+
+        // EXIF中没有作者和版权时使用XMP中的值
+        let xmp_only = SourceMetadata { xmp: Some(SAMPLE_XMP.as_bytes().to_vec()), ..Default::default() }
+            .filtered(MetadataPolicy::CopyrightOnly);
+        assert_eq!(xmp_only.artist.as_deref(), Some("Carol & Dan"));
+        assert_eq!(xmp_only.copyright.as_deref(), Some("(c) Eve"));
+        let xmp = String::from_utf8(xmp_only.xmp.unwrap()).unwrap();
+        assert!(xmp.contains("Carol &amp; Dan") && !xmp.contains("GPS"), "{}", xmp);
+    }
+
+    fn encoded(format: image::ImageFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        DynamicImage::new_rgb8(16, 8).write_to(&mut std::io::Cursor::new(&mut data), format).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_embedded_metadata_is_readable() {
+        let metadata = SourceMetadata {
+            exif: Some(sample_exif()),
+            icc_profile: Some(vec![7; 300]),
+            xmp: Some(SAMPLE_XMP.as_bytes().to_vec()),
+        }
+        .filtered(MetadataPolicy::KeepAll);
+
+        for (container, format) in [
+            (Container::Jpeg, image::ImageFormat::Jpeg),
+            (Container::Png, image::ImageFormat::Png),
+            (Container::WebP, image::ImageFormat::WebP),
+        ] {
+            let data = embed(encoded(format), container, &metadata).unwrap();
+            let mut decoder = image::ImageReader::with_format(std::io::Cursor::new(&data), format)
+                .into_decoder()
+                .unwrap();
+            assert_eq!(decoder.dimensions(), (16, 8), "{:?}", container);
+            let exif = match container {
+                Container::Png => read_png_exif(&data),
+                _ => decoder.exif_metadata().unwrap(),
+            };
+            assert_eq!(exif, metadata.exif, "{:?}", container);
+            assert_eq!(decoder.icc_profile().unwrap(), metadata.icc_profile, "{:?}", container);
+            assert_eq!(read_xmp(&data), metadata.xmp, "{:?}", container);
+            assert_eq!(data.len() - encoded(format).len(), embedded_size(container, &metadata).unwrap(), "{:?}", container);
+        }
+
+        // 截断的 VP8 块返回错误而不是越界
+        let truncated = b"RIFF\x14\0\0\0WEBPVP8 \x07\0\0\0\0\0\0\x9d\x01\x2a\x10\0";
+        assert!(matches!(embed(truncated.to_vec(), Container::WebP, &metadata), Err(ConvertError::Metadata { .. })));
+    }
+
+    #[test]
+    fn test_png_copyright_text_chunks() {
+        let metadata = SourceMetadata { exif: Some(sample_exif()), ..Default::default() }
+            .filtered(MetadataPolicy::CopyrightOnly);
+        let data = embed(encoded(image::ImageFormat::Png), Container::Png, &metadata).unwrap();
+
+        let reader = png::Decoder::new(std::io::Cursor::new(&data)).read_info().unwrap();
+        let texts: Vec<(String, String)> = reader.info().utf8_text.iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.get_text().unwrap()))
+            .collect();
+        assert_eq!(texts, vec![
+            ("Author".to_string(), "Alice".to_string()),
+            ("Copyright".to_string(), "(c) Bob".to_string()),
+            ("XML:com.adobe.xmp".to_string(), String::from_utf8(metadata.xmp.unwrap()).unwrap()),
+        ]);
+    }
+}
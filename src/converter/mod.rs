@@ -1,8 +1,26 @@
+pub mod avif_encoder;
 pub mod batch_processor;
+pub mod cancel;
+pub mod concurrency;
+pub mod discovery;
+pub mod error;
 pub mod image_converter;
+pub mod image_loader;
+pub mod journal;
 pub mod pdf_converter;
+pub mod pdf_images;
+pub mod pdf_text;
+pub mod pdf_to_image;
+pub mod png_quantizer;
+pub mod progress;
+pub mod pure_watermark;
+pub mod resize;
 pub mod simd_optimizer;
 pub mod turbo_encoder;
 pub mod webp_encoder;
 pub mod simple_watermark;
-pub mod image_to_pdf;
\ No newline at end of file
+pub mod image_to_pdf;
+pub mod metadata;
+pub mod naming;
+pub mod output_guard;
+pub mod page_range;
\ No newline at end of file
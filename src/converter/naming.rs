@@ -0,0 +1,208 @@
+// 输出文件命名模板 - 所有处理模式共用同一套占位符
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 模板中可用的占位符，界面提示和文档使用
+pub const TEMPLATE_TOKENS: &str =
+    "{stem} {ext} {page} {page:03} {index} {date} {width}x{height} {format}";
+
+/// 输出文件命名设置
+///
+/// 模板只决定文件名主体，扩展名始终由输出格式决定。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NamingSettings {
+    /// 图片输出文件名模板
+    pub image_template: String,
+    /// PDF页面输出文件名模板，应包含 `{page}` 以免页面互相覆盖
+    pub pdf_page_template: String,
+}
+
+impl Default for NamingSettings {
+    fn default() -> Self {
+        Self {
+            image_template: "{stem}".to_string(),
+            pdf_page_template: "{stem}_page_{page:03}".to_string(),
+        }
+    }
+}
+
+impl NamingSettings {
+    /// 生成完整的输出文件名（含扩展名）
+    ///
+    /// 有页码时使用PDF页面模板，否则使用图片模板。
+    pub fn file_name(&self, context: &NameContext) -> String {
+        let template = match context.page {
+            Some(_) => &self.pdf_page_template,
+            None => &self.image_template,
+        };
+        format!("{}.{}", render(template, context), context.format)
+    }
+
+    /// 生成PDF内嵌图片的输出文件名：PDF页面模板后追加图片对象序号
+    pub fn embedded_image_name(&self, context: &NameContext, object: &str) -> String {
+        format!("{}_img_{}.{}", render(&self.pdf_page_template, context), object, context.format)
+    }
+}
+
+/// 渲染模板所需的信息
+#[derive(Debug, Clone)]
+pub struct NameContext<'a> {
+    /// 源文件路径，提供 `{stem}` 和 `{ext}`
+    pub source: &'a Path,
+    /// 从1开始的页码
+    pub page: Option<usize>,
+    /// 文件在本批次中从1开始的序号
+    pub index: usize,
+    /// 输出图像尺寸
+    pub width: u32,
+    pub height: u32,
+    /// 输出格式的扩展名
+    pub format: &'a str,
+    pub date: NaiveDate,
+}
+
+impl<'a> NameContext<'a> {
+    pub fn new(source: &'a Path, index: usize, format: &'a str) -> Self {
+        Self {
+            source,
+            page: None,
+            index,
+            width: 0,
+            height: 0,
+            format,
+            date: Local::now().date_naive(),
+        }
+    }
+
+    pub fn with_page(mut self, page: usize) -> Self {
+        self.page = Some(page);
+        self
+    }
+
+    pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+}
+
+/// 按上下文替换模板中的占位符
+///
+/// 数字占位符支持 `{page:03}` 形式的补零宽度；未知占位符原样保留。
+/// 结果中的路径分隔符和文件名非法字符会被替换为 `_`，为空时退回源文件名。
+pub fn render(template: &str, context: &NameContext) -> String {
+    let mut output = String::with_capacity(template.len() + 16);
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let Some(end) = after.find('}') else {
+            output.push_str(after);
+            rest = "";
+            break;
+        };
+
+        let token = &after[1..end];
+        match expand(token, context) {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&after[..=end]),
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+
+    let name = sanitize(&output);
+    if name.is_empty() {
+        sanitize(&stem(context.source))
+    } else {
+        name
+    }
+}
+
+/// 展开单个占位符，未知占位符返回 None
+fn expand(token: &str, context: &NameContext) -> Option<String> {
+    let (name, width) = match token.split_once(':') {
+        Some((name, spec)) => (name, Some(spec.parse::<usize>().ok()?)),
+        None => (token, None),
+    };
+
+    let number = |value: usize| match width {
+        Some(width) => format!("{:0width$}", value, width = width),
+        None => value.to_string(),
+    };
+
+    let value = match name {
+        "stem" => stem(context.source),
+        "ext" => context.source.extension().unwrap_or_default().to_string_lossy().into_owned(),
+        "page" => number(context.page.unwrap_or(1)),
+        "index" => number(context.index),
+        "width" => number(context.width as usize),
+        "height" => number(context.height as usize),
+        "date" => context.date.format("%Y%m%d").to_string(),
+        "format" => context.format.to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// 替换文件名中不允许出现的字符
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(source: &Path) -> NameContext<'_> {
+        NameContext {
+            date: NaiveDate::from_ymd_opt(2024, 3, 9).unwrap(),
+            ..NameContext::new(source, 7, "webp")
+        }
+    }
+
+    #[test]
+    fn test_render_tokens() {
+        let source = Path::new("/photos/holiday.PNG");
+        let ctx = context(source).with_page(5).with_dimensions(800, 600);
+
+        assert_eq!(render("{stem}", &ctx), "holiday");
+        assert_eq!(render("{stem}_{ext}_{format}", &ctx), "holiday_PNG_webp");
+        assert_eq!(render("{stem}_page_{page:03}", &ctx), "holiday_page_005");
+        assert_eq!(render("{index:04}-{date}", &ctx), "0007-20240309");
+        assert_eq!(render("{stem}_{width}x{height}", &ctx), "holiday_800x600");
+        // 未知占位符和未闭合的括号原样保留
+        assert_eq!(render("{stem}_{unknown}_{page", &ctx), "holiday_{unknown}_{page");
+    }
+
+    #[test]
+    fn test_file_name_sanitizes_and_falls_back() {
+        let source = Path::new("scan.pdf");
+        let naming = NamingSettings::default();
+        assert_eq!(naming.file_name(&context(source).with_page(12)), "scan_page_012.webp");
+        assert_eq!(naming.file_name(&context(source)), "scan.webp");
+        assert_eq!(naming.embedded_image_name(&context(source).with_page(3), "4-2"), "scan_page_003_img_4-2.webp");
+
+        let naming = NamingSettings { image_template: "a/b:{stem}".to_string(), ..Default::default() };
+        assert_eq!(naming.file_name(&context(source)), "a_b_scan.webp");
+
+        let naming = NamingSettings { image_template: "  ".to_string(), ..Default::default() };
+        assert_eq!(naming.file_name(&context(source)), "scan.webp");
+    }
+}
@@ -0,0 +1,245 @@
+// 输出冲突处理 - 决定输出文件已存在时的行为，并防止误覆盖源文件
+
+use crate::converter::error::{ConvertError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// 替换原文件模式下，原文件备份到源文件所在目录下的这个隐藏文件夹
+pub const BACKUP_DIR_NAME: &str = ".image_converter_backup";
+
+/// 输出文件已存在时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum CollisionPolicy {
+    /// 覆盖已有文件（源文件除外）
+    #[default]
+    Overwrite,
+    /// 保留已有文件，跳过本次输出
+    Skip,
+    /// 自动重命名为 `name (1).ext`
+    Rename,
+    /// 报错，该文件计为失败
+    Fail,
+}
+
+impl CollisionPolicy {
+    /// 获取所有可用策略
+    pub fn all_policies() -> Vec<(Self, &'static str)> {
+        vec![
+            (CollisionPolicy::Overwrite, "覆盖"),
+            (CollisionPolicy::Skip, "跳过"),
+            (CollisionPolicy::Rename, "自动重命名"),
+            (CollisionPolicy::Fail, "报错"),
+        ]
+    }
+}
+
+/// 输出写入设置
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    /// 输出文件已存在时的处理方式
+    pub collision: CollisionPolicy,
+    /// 用处理结果替换原文件：输出写到源文件所在目录，成功后移除原文件；
+    /// 开启“保留原始文件”时原文件先备份到 [`BACKUP_DIR_NAME`]
+    pub replace_originals: bool,
+}
+
+/// 一次批处理的输出路径分配器
+///
+/// 记录本次运行的所有源文件和已分配的输出路径，多个工作线程可以安全地共享。
+#[derive(Debug)]
+pub struct OutputGuard {
+    settings: OutputSettings,
+    keep_originals: bool,
+    sources: HashSet<PathBuf>,
+    reserved: Mutex<HashSet<PathBuf>>,
+}
+
+impl OutputGuard {
+    pub fn new(settings: OutputSettings, keep_originals: bool, sources: &[PathBuf]) -> Self {
+        Self {
+            settings,
+            keep_originals,
+            sources: sources.iter().map(|path| normalize(path)).collect(),
+            reserved: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// 是否为替换原文件模式
+    pub fn replaces_originals(&self) -> bool {
+        self.settings.replace_originals
+    }
+
+    /// 为 `source` 的输出分配最终路径，返回 None 表示按策略跳过
+    ///
+    /// 替换原文件模式下 `target` 可以是源文件本身，此时会先按设置备份原文件；
+    /// 其余情况绝不会返回任何一个源文件的路径。
+    pub fn resolve(&self, source: &Path, target: PathBuf) -> Result<Option<PathBuf>> {
+        let normalized = normalize(&target);
+        let mut reserved = self.reserved.lock().unwrap();
+
+        let replacing_source = self.settings.replace_originals && normalized == normalize(source);
+        if replacing_source {
+            if self.keep_originals {
+                backup(source)?;
+            }
+            reserved.insert(normalized);
+            return Ok(Some(target));
+        }
+
+        let is_source = self.sources.contains(&normalized);
+        let exists = is_source || reserved.contains(&normalized) || target.exists();
+        if !exists {
+            reserved.insert(normalized);
+            return Ok(Some(target));
+        }
+
+        match self.settings.collision {
+            CollisionPolicy::Overwrite if is_source => Err(ConvertError::WouldOverwriteSource { path: target }),
+            CollisionPolicy::Overwrite => {
+                reserved.insert(normalized);
+                Ok(Some(target))
+            },
+            CollisionPolicy::Skip => Ok(None),
+            CollisionPolicy::Fail => Err(ConvertError::OutputExists { path: target }),
+            CollisionPolicy::Rename => {
+                let renamed = (1..)
+                    .map(|n| numbered(&target, n))
+                    .find(|candidate| {
+                        let normalized = normalize(candidate);
+                        !self.sources.contains(&normalized) && !reserved.contains(&normalized) && !candidate.exists()
+                    })
+                    .expect("无限序列中总能找到可用的文件名");
+                reserved.insert(normalize(&renamed));
+                Ok(Some(renamed))
+            },
+        }
+    }
+
+    /// 输出写入成功后调用：替换原文件模式下，输出与原文件不同名时移除原文件
+    ///
+    /// 开启“保留原始文件”时原文件会先备份再移除。
+    pub fn finish(&self, source: &Path, written: &Path) -> Result<()> {
+        if !self.settings.replace_originals || normalize(written) == normalize(source) {
+            return Ok(());
+        }
+        if self.keep_originals {
+            backup(source)?;
+        }
+        std::fs::remove_file(source).map_err(|e| ConvertError::io(source, e))
+    }
+}
+
+/// 复制原文件到同目录下的备份文件夹，已有同名备份时不覆盖
+fn backup(source: &Path) -> Result<()> {
+    let parent = source.parent().unwrap_or_else(|| Path::new("."));
+    let backup_dir = parent.join(BACKUP_DIR_NAME);
+    std::fs::create_dir_all(&backup_dir).map_err(|e| ConvertError::io(&backup_dir, e))?;
+
+    let file_name = source.file_name().unwrap_or_default();
+    let mut backup_path = backup_dir.join(file_name);
+    let mut n = 1;
+    while backup_path.exists() {
+        backup_path = numbered(&backup_dir.join(file_name), n);
+        n += 1;
+    }
+    std::fs::copy(source, &backup_path).map_err(|e| ConvertError::io(&backup_path, e))?;
+    Ok(())
+}
+
+/// `photo.jpg` -> `photo (n).jpg`
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(ext) => format!("{} ({}).{}", stem, n, ext.to_string_lossy()),
+        None => format!("{} ({})", stem, n),
+    };
+    path.with_file_name(file_name)
+}
+
+/// 规范化路径以便比较；文件尚不存在时规范化其所在目录
+fn normalize(path: &Path) -> PathBuf {
+    if let Ok(canonical) = path.canonicalize() {
+        return canonical;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|parent| parent.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("output_guard_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_collision_policies() {
+        let dir = temp_dir("policies");
+        let source = dir.join("photo.png");
+        let existing = dir.join("photo.jpg");
+        std::fs::write(&source, b"source").unwrap();
+        std::fs::write(&existing, b"old").unwrap();
+        let sources = vec![source.clone()];
+
+        let guard = |collision| OutputGuard::new(OutputSettings { collision, ..Default::default() }, true, &sources);
+
+        let renamer = guard(CollisionPolicy::Rename);
+        assert_eq!(renamer.resolve(&source, existing.clone()).unwrap(), Some(dir.join("photo (1).jpg")));
+        // 同一次运行中已分配的路径也视为冲突
+        assert_eq!(renamer.resolve(&source, existing.clone()).unwrap(), Some(dir.join("photo (2).jpg")));
+
+        assert_eq!(guard(CollisionPolicy::Skip).resolve(&source, existing.clone()).unwrap(), None);
+        assert_eq!(guard(CollisionPolicy::Overwrite).resolve(&source, existing.clone()).unwrap(), Some(existing.clone()));
+        assert!(matches!(
+            guard(CollisionPolicy::Fail).resolve(&source, existing.clone()),
+            Err(ConvertError::OutputExists { .. })
+        ));
+        // 覆盖策略也不允许写到源文件上
+        assert!(matches!(
+            guard(CollisionPolicy::Overwrite).resolve(&source, source.clone()),
+            Err(ConvertError::WouldOverwriteSource { .. })
+        ));
+        // 旧配置没有该字段时保持之前的覆盖行为
+        let settings: OutputSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(settings.collision, CollisionPolicy::Overwrite);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_replace_originals_with_backup() {
+        let dir = temp_dir("replace");
+        let source = dir.join("photo.png");
+        std::fs::write(&source, b"source").unwrap();
+        let settings = OutputSettings { replace_originals: true, ..Default::default() };
+        let guard = OutputGuard::new(settings, true, std::slice::from_ref(&source));
+
+        let output = guard.resolve(&source, dir.join("photo.jpg")).unwrap().unwrap();
+        std::fs::write(&output, b"converted").unwrap();
+        guard.finish(&source, &output).unwrap();
+
+        assert!(!source.exists());
+        assert_eq!(std::fs::read(dir.join(BACKUP_DIR_NAME).join("photo.png")).unwrap(), b"source");
+
+        // 同名输出直接替换原文件，不保留原始文件时不做备份
+        let source = dir.join("logo.png");
+        std::fs::write(&source, b"source").unwrap();
+        let guard = OutputGuard::new(settings, false, std::slice::from_ref(&source));
+        assert_eq!(guard.resolve(&source, source.clone()).unwrap(), Some(source.clone()));
+        assert!(!dir.join(BACKUP_DIR_NAME).join("logo.png").exists());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,95 @@
+// PDF页面范围 - 解析 `1-3,7,10-` 形式的页面选择
+
+use crate::converter::error::{ConvertError, Result};
+
+/// 要处理的PDF页面（页码从1开始）
+///
+/// 由逗号分隔的单页 `7`、闭区间 `1-3`、开放区间 `10-`（到最后一页）和 `-5`（从第一页）组成，
+/// 空字符串表示全部页面。超出文档页数的部分被忽略。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageRange {
+    /// 各段的起止页码（含），`None` 表示到最后一页；为空时选择全部页面
+    spans: Vec<(usize, Option<usize>)>,
+}
+
+impl PageRange {
+    /// 解析页面范围描述，格式错误时返回 [`ConvertError::InvalidPageRange`]
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = |reason| ConvertError::InvalidPageRange { spec: spec.to_string(), reason };
+        let parse_page = |text: &str| -> Result<usize> {
+            match text.trim().parse::<usize>() {
+                Ok(0) => Err(invalid("页码从1开始")),
+                Ok(page) => Ok(page),
+                Err(_) => Err(invalid("页码必须是正整数")),
+            }
+        };
+
+        let mut spans = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let span = match part.split_once('-') {
+                None => {
+                    let page = parse_page(part)?;
+                    (page, Some(page))
+                },
+                Some((start, end)) => {
+                    let start = if start.trim().is_empty() { 1 } else { parse_page(start)? };
+                    let end = if end.trim().is_empty() { None } else { Some(parse_page(end)?) };
+                    if end.is_some_and(|end| end < start) {
+                        return Err(invalid("区间的结束页不能小于起始页"));
+                    }
+                    (start, end)
+                },
+            };
+            spans.push(span);
+        }
+        Ok(Self { spans })
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// 第 `page` 页是否被选中
+    pub fn contains(&self, page: usize) -> bool {
+        self.is_all() || self.spans.iter().any(|&(start, end)| page >= start && end.is_none_or(|end| page <= end))
+    }
+
+    /// 共 `total` 页的文档中被选中的页码，按顺序排列且不重复
+    pub fn pages(&self, total: usize) -> Vec<usize> {
+        (1..=total).filter(|&page| self.contains(page)).collect()
+    }
+
+    /// 共 `total` 页的文档中被选中的页数
+    pub fn count(&self, total: usize) -> usize {
+        (1..=total).filter(|&page| self.contains(page)).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_select_pages() {
+        let range = PageRange::parse("1-3, 7,10-").unwrap();
+        assert_eq!(range.pages(12), vec![1, 2, 3, 7, 10, 11, 12]);
+        // 超出文档的页码被忽略
+        assert_eq!(range.pages(5), vec![1, 2, 3]);
+        assert_eq!(range.count(8), 4);
+
+        // 重叠区间不会重复选择
+        assert_eq!(PageRange::parse("2-4,3,-2").unwrap().pages(10), vec![1, 2, 3, 4]);
+
+        assert!(PageRange::parse("").unwrap().is_all());
+        assert!(PageRange::parse(" , ").unwrap().is_all());
+        assert_eq!(PageRange::default().count(3), 3);
+
+        for invalid in ["0", "3-1", "a", "1-b", "1--2"] {
+            assert!(
+                matches!(PageRange::parse(invalid), Err(ConvertError::InvalidPageRange { .. })),
+                "{} 应当无效",
+                invalid
+            );
+        }
+    }
+}
@@ -1,68 +1,391 @@
-use anyhow::{anyhow, Context, Result};
+use crate::converter::cancel::CancellationToken;
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::page_range::PageRange;
+use crate::converter::pdf_text::{self, PageText};
 use image::DynamicImage;
 use pdfium_render::prelude::*;
-use std::path::Path; // --- 【API修复】引入 Path 类型 ---
-
-/// 将PDF文件转换为一系列图像。
-pub fn convert_pdf_to_images(
-    pdf_path: &Path,
-    dpi: f32,
-) -> Result<Vec<DynamicImage>> {
-    // --- 【线程安全修复】在函数内部创建 Pdfium 实例，确保线程安全 ---
-    // 1. 获取库的绑定
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// PDF页面背景
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum PageBackground {
+    /// 白色背景
+    #[default]
+    White,
+    /// 透明背景，只在PNG、WebP、AVIF等带透明通道的格式中保留，JPEG输出为白色
+    Transparent,
+    /// 自定义颜色，见 [`PdfRenderSettings::background_color`]
+    Color,
+}
+
+impl PageBackground {
+    /// 获取所有可用背景
+    pub fn all_backgrounds() -> Vec<(Self, &'static str)> {
+        vec![
+            (PageBackground::White, "白色"),
+            (PageBackground::Transparent, "透明"),
+            (PageBackground::Color, "自定义颜色"),
+        ]
+    }
+}
+
+/// PDF转图片的输出内容
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum PdfOutput {
+    /// 按渲染设置把每页渲染为图片
+    #[default]
+    RenderPages,
+    /// 按原始分辨率和编码导出页面中嵌入的图片，不重新渲染和压缩
+    EmbeddedImages,
+}
+
+impl PdfOutput {
+    /// 获取所有可用输出内容
+    pub fn all_outputs() -> Vec<(Self, &'static str)> {
+        vec![
+            (PdfOutput::RenderPages, "渲染页面"),
+            (PdfOutput::EmbeddedImages, "提取内嵌图片"),
+        ]
+    }
+}
+
+/// PDF页面渲染尺寸
+///
+/// 按像素尺寸渲染时，同一文档中页面尺寸不同的页面也输出为一致的宽度或高度。
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum RenderSize {
+    /// 按DPI渲染，输出尺寸随页面尺寸变化
+    #[default]
+    Dpi,
+    /// 固定宽度，高度按页面比例计算
+    Width,
+    /// 固定高度，宽度按页面比例计算
+    Height,
+    /// 保持比例，缩放到宽×高范围内
+    Fit,
+}
+
+impl RenderSize {
+    /// 获取所有可用模式
+    pub fn all_modes() -> Vec<(Self, &'static str)> {
+        vec![
+            (RenderSize::Dpi, "按DPI"),
+            (RenderSize::Width, "固定宽度"),
+            (RenderSize::Height, "固定高度"),
+            (RenderSize::Fit, "适应宽×高"),
+        ]
+    }
+}
+
+/// PDF页面渲染设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfRenderSettings {
+    /// 要渲染的页面，如 `1-3,7,10-`，为空时渲染全部页面
+    pub page_range: String,
+    /// 加密PDF的默认密码，为空表示不使用密码
+    ///
+    /// 密码只从配置文件读取，保存配置时不会写回磁盘。
+    #[serde(skip_serializing)]
+    pub password: String,
+    /// 按文件指定的密码，键为文件路径或文件名，优先于默认密码
+    #[serde(skip_serializing)]
+    pub file_passwords: BTreeMap<String, String>,
+    /// 灰度渲染
+    pub grayscale: bool,
+    /// 页面背景
+    pub background: PageBackground,
+    /// 自定义背景色（RGB）
+    pub background_color: [u8; 3],
+    /// 渲染注释（批注、高亮、印章等）
+    pub render_annotations: bool,
+    /// 渲染表单域及其中填写的内容（包括签名域）
+    pub render_form_fields: bool,
+    /// 输出尺寸：按DPI或按像素尺寸
+    pub size_mode: RenderSize,
+    /// 目标宽度（像素），用于固定宽度和适应宽×高
+    pub target_width: u32,
+    /// 目标高度（像素），用于固定高度和适应宽×高
+    pub target_height: u32,
+    /// PDF转图片输出渲染的页面还是内嵌图片
+    pub output: PdfOutput,
+}
+
+impl Default for PdfRenderSettings {
+    fn default() -> Self {
+        Self {
+            page_range: String::new(),
+            password: String::new(),
+            file_passwords: BTreeMap::new(),
+            grayscale: false,
+            background: PageBackground::White,
+            background_color: [255, 255, 255],
+            render_annotations: true,
+            render_form_fields: true,
+            size_mode: RenderSize::Dpi,
+            target_width: 1600,
+            target_height: 1600,
+            output: PdfOutput::RenderPages,
+        }
+    }
+}
+
+impl PdfRenderSettings {
+    /// 解析页面范围
+    pub fn pages(&self) -> Result<PageRange> {
+        PageRange::parse(&self.page_range)
+    }
+
+    /// 按设置生成 PDFium 渲染参数，`dpi` 只在按DPI渲染时使用
+    pub fn render_config(&self, dpi: f32) -> PdfRenderConfig {
+        let clear_color = match self.background {
+            PageBackground::White => PdfColor::WHITE,
+            // 透明像素的颜色仍为白色，转换为不带透明通道的格式时显示为白底
+            PageBackground::Transparent => PdfColor::new(255, 255, 255, 0),
+            PageBackground::Color => {
+                let [red, green, blue] = self.background_color;
+                PdfColor::new(red, green, blue, 255)
+            },
+        };
+        let (width, height) = (pixels(self.target_width), pixels(self.target_height));
+        let config = match self.size_mode {
+            RenderSize::Dpi => PdfRenderConfig::new().scale_page_by_factor(dpi / 72.0),
+            RenderSize::Width => PdfRenderConfig::new().set_target_width(width),
+            RenderSize::Height => PdfRenderConfig::new().set_target_height(height),
+            // 先按宽度缩放，超出高度时再按高度缩小，保持页面比例
+            RenderSize::Fit => PdfRenderConfig::new()
+                .set_target_width(width)
+                .set_maximum_width(width)
+                .set_maximum_height(height),
+        };
+        config
+            .use_grayscale_rendering(self.grayscale)
+            .set_clear_color(clear_color)
+            .render_annotations(self.render_annotations)
+            .render_form_data(self.render_form_fields)
+    }
+
+    /// 影响渲染结果的设置，用于任务日志的设置摘要
+    pub fn output_digest(&self) -> String {
+        format!(
+            "{:?}",
+            (
+                self.grayscale,
+                self.background,
+                self.background_color,
+                self.render_annotations,
+                self.render_form_fields,
+                self.size_mode,
+                self.target_width,
+                self.target_height,
+            )
+        )
+    }
+
+    /// 打开 `path` 时使用的密码：先按完整路径、再按文件名查找，最后使用默认密码
+    pub fn password_for(&self, path: &Path) -> Option<&str> {
+        let by_path = self.file_passwords.get(path.to_string_lossy().as_ref());
+        let by_name = || {
+            let name = path.file_name()?.to_string_lossy();
+            self.file_passwords.get(name.as_ref())
+        };
+        by_path
+            .or_else(by_name)
+            .map(String::as_str)
+            .or_else(|| Some(self.password.as_str()).filter(|password| !password.is_empty()))
+    }
+}
+
+/// 像素尺寸限制在 PDFium 位图支持的范围内
+fn pixels(value: u32) -> Pixels {
+    value.clamp(1, 65_535) as Pixels
+}
+
+/// 进程内共享的 PDFium 实例
+///
+/// PDFium 库只能初始化一次：每个实例在释放时都会销毁整个库，
+/// 多个实例并存会让其他线程正在使用的文档失效。
+static PDFIUM: OnceLock<Pdfium> = OnceLock::new();
+
+/// 串行化首次绑定，避免多个线程同时初始化库
+static PDFIUM_INIT: Mutex<()> = Mutex::new(());
+
+/// 获取共享的 PDFium 实例，首次调用时绑定程序目录或系统中的动态库
+///
+/// 绑定失败不会被缓存，安装动态库后再次调用即可成功。
+/// 启用了 pdfium-render 的 `sync` 特性（包含 `thread_safe`），所有调用都经由库内部的锁串行执行。
+pub fn pdfium() -> Result<&'static Pdfium> {
+    if let Some(pdfium) = PDFIUM.get() {
+        return Ok(pdfium);
+    }
+    let _init = PDFIUM_INIT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(pdfium) = PDFIUM.get() {
+        return Ok(pdfium);
+    }
     let bindings = Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./"))
         .or_else(|_| Pdfium::bind_to_system_library())
-        .context("❌ PDFium库初始化失败！\n\n📋 解决方案：\n1. 下载pdfium.dll文件到程序目录\n2. 下载地址: https://github.com/bblanchon/pdfium-binaries/releases\n3. 选择Windows x64版本\n4. 解压后将pdfium.dll复制到exe同目录\n\n或者重新编译程序使用静态链接")?;
-    
-    // 2. 使用绑定来创建一个 Pdfium 实例
-    let pdfium = Pdfium::new(bindings);
+        .map_err(|source| ConvertError::PdfiumUnavailable { source })?;
+    Ok(PDFIUM.get_or_init(|| Pdfium::new(bindings)))
+}
 
-    // 加载 PDF 文档
-    let document = pdfium.load_pdf_from_file(pdf_path, None)
-        .map_err(|e| anyhow!("无法加载PDF文件 '{}': {}", pdf_path.display(), e))?;
+/// 把文档加载失败转换为错误，密码错误按是否提供了密码区分
+fn load_error(path: &Path, has_password: bool, source: PdfiumError) -> ConvertError {
+    match source {
+        PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::PasswordError) if has_password => {
+            ConvertError::PdfPasswordIncorrect { path: path.to_path_buf() }
+        },
+        PdfiumError::PdfiumLibraryInternalError(PdfiumInternalError::PasswordError) => {
+            ConvertError::PdfPasswordRequired { path: path.to_path_buf() }
+        },
+        source => ConvertError::PdfLoad { path: path.to_path_buf(), source },
+    }
+}
 
-    let render_config = PdfRenderConfig::new()
-        .scale_page_by_factor(dpi / 72.0);
+/// 已打开的PDF文档，页数查询和渲染共用同一次加载
+pub struct PdfFile<'a> {
+    path: PathBuf,
+    document: PdfDocument<'a>,
+}
 
-    // --- 【内存优化】改为逐页处理，避免一次性加载所有页面到内存 ---
-    let mut images = Vec::new();
+impl<'a> PdfFile<'a> {
+    /// 打开PDF文件，加密文件使用 `password` 解锁
+    ///
+    /// 密码缺失或错误时分别返回 [`ConvertError::PdfPasswordRequired`] 和
+    /// [`ConvertError::PdfPasswordIncorrect`]，其他加载失败返回 [`ConvertError::PdfLoad`]。
+    pub fn open(path: &Path, password: Option<&'a str>) -> Result<Self> {
+        let document = pdfium()?
+            .load_pdf_from_file(path, password)
+            .map_err(|source| load_error(path, password.is_some(), source))?;
+        Ok(Self { path: path.to_path_buf(), document })
+    }
+
+    /// 文档总页数
+    pub fn page_count(&self) -> usize {
+        self.document.pages().len().into()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 底层的 pdfium-render 文档，供需要直接调用 PDFium 接口的模块使用
+    pub(crate) fn document(&self) -> &PdfDocument<'a> {
+        &self.document
+    }
+
+    /// 按 `render_config` 逐页渲染 `pages` 中的页面，每渲染完一页立即交给 `on_page` 处理
+    ///
+    /// `pages` 为从1开始且不超过文档页数的页码，通常由 [`PageRange::pages`] 生成，
+    /// 调用方可以先去掉不需要重新渲染的页面（如继续任务时已完成的页面）。
+    ///
+    /// `on_page` 收到从1开始的页码和该页的渲染结果，返回后页面图像即被释放，
+    /// 同一时间只有一页保存在内存中。单页渲染失败不会中断整个文档，由 `on_page` 决定如何处理；
+    /// `on_page` 返回错误时停止渲染并返回该错误。每页渲染前检查 `cancel`，
+    /// 取消时返回 [`ConvertError::Cancelled`]。
+    ///
+    /// 返回成功渲染的页数。`pages` 为空时返回 0；
+    /// 页面全部渲染失败时返回 [`ConvertError::NoRenderablePages`]。
+    pub fn render_pages(
+        &self,
+        render_config: &PdfRenderConfig,
+        pages: &[usize],
+        cancel: &CancellationToken,
+        mut on_page: impl FnMut(usize, Result<DynamicImage>) -> Result<()>,
+    ) -> Result<usize> {
+        let selected = pages;
+        if selected.is_empty() {
+            return Ok(0);
+        }
+        let mut rendered = 0;
 
-    for (page_index, page) in document.pages().iter().enumerate() {
-        match page.render_with_config(&render_config) {
-            Ok(bitmap) => {
-                let image = bitmap.as_image();
-                images.push(image);
+        for (done, &page_number) in selected.iter().enumerate() {
+            cancel.check()?;
+            let render_error = |source| ConvertError::PdfRender { path: self.path.clone(), page: page_number, source };
+            // 页码不超过文档页数，转换为 PDFium 的16位页面索引不会溢出
+            let image = self.document.pages().get((page_number - 1) as PdfPageIndex)
+                .and_then(|page| page.render_with_config(render_config).map(|bitmap| bitmap.as_image()))
+                .map_err(render_error);
+            if image.is_ok() {
+                rendered += 1;
                 // 每10页输出一次进度信息，避免日志过多
-                let total_pages = document.pages().len() as usize;
-                if page_index % 10 == 0 || page_index == total_pages - 1 {
-                    println!("  📄 已渲染页面 {}/{}", page_index + 1, total_pages);
+                if done.is_multiple_of(10) || done == selected.len() - 1 {
+                    println!("  📄 已渲染页面 {}/{}", done + 1, selected.len());
                 }
-            },
-            Err(e) => {
-                eprintln!("⚠️  跳过页面 {}: 渲染失败 - {}", page_index + 1, e);
-                continue; // 跳过有问题的页面，继续处理其他页面
             }
+            on_page(page_number, image)?;
+        }
+
+        if rendered == 0 {
+            Err(ConvertError::NoRenderablePages { path: self.path.clone() })
+        } else {
+            Ok(rendered)
         }
     }
 
-    if images.is_empty() {
-        Err(anyhow!("PDF文件 '{}' 中没有可渲染的页面。", pdf_path.display()))
-    } else {
-        Ok(images)
+    /// 读取第 `page` 页（从1开始）的文本，`word_boxes` 为 true 时同时切分单词并计算边界框
+    pub fn page_text(&self, page: usize, word_boxes: bool) -> Result<PageText> {
+        let error = |reason| ConvertError::PdfExtract { path: self.path.clone(), page, reason };
+        // 页码不超过文档页数，转换为 PDFium 的16位页面索引不会溢出
+        let pdf_page = self.document.pages().get((page - 1) as PdfPageIndex).map_err(|_| error("页面无法加载"))?;
+        let text = pdf_page.text().map_err(|_| error("文本无法读取"))?;
+        Ok(PageText {
+            page,
+            width: pdf_page.width().value,
+            height: pdf_page.height().value,
+            text: text.all(),
+            words: if word_boxes { pdf_text::words(&text) } else { Vec::new() },
+        })
     }
 }
 
-/// 快速获取PDF文件的总页数
-pub fn get_pdf_page_count(pdf_path: &Path) -> Result<usize> {
-    // --- 【线程安全修复】同样在函数内部创建独立的 Pdfium 实例 ---
-    let bindings = Pdfium::bind_to_library(Pdfium::pdfium_platform_library_name_at_path("./"))
-        .or_else(|_| Pdfium::bind_to_system_library())
-        .context("❌ PDFium库初始化失败！请下载pdfium.dll到程序目录")?;
-    let pdfium = Pdfium::new(bindings);
-    
-    let document = pdfium.load_pdf_from_file(pdf_path, None)
-        .map_err(|e| anyhow!("无法加载PDF文件 '{}': {}", pdf_path.display(), e))?;
-    
-    // --- 【类型修复】将 u16 转换为 usize ---
-    Ok(document.pages().len().into())
-}
\ No newline at end of file
+/// 将PDF文件中 `pages` 选中的页面按 `dpi` 渲染为图像，跳过无法渲染的页面
+///
+/// 所有页面图像同时保存在内存中，页数较多时应使用 [`PdfFile::render_pages`] 逐页处理。
+/// 选中的页面全部渲染失败时返回 [`ConvertError::NoRenderablePages`]。
+#[allow(dead_code)]
+pub fn convert_pdf_to_images(pdf_path: &Path, dpi: f32, pages: &PageRange) -> Result<Vec<DynamicImage>> {
+    let document = PdfFile::open(pdf_path, None)?;
+    let render_config = PdfRenderConfig::new().scale_page_by_factor(dpi / 72.0);
+    let mut images = Vec::new();
+    document.render_pages(&render_config, &pages.pages(document.page_count()), &CancellationToken::new(), |page, image| {
+        match image {
+            Ok(image) => images.push(image),
+            Err(e) => eprintln!("⚠️  跳过页面 {}: {}", page, e.chain()),
+        }
+        Ok(())
+    })?;
+    Ok(images)
+}
+
+/// 快速获取PDF文件中 `pages` 选中的页数
+///
+/// 每次调用都会打开文件；之后还要渲染的文件应直接使用 [`PdfFile`]，页数和渲染共用同一次加载。
+#[allow(dead_code)]
+pub fn get_pdf_page_count(pdf_path: &Path, pages: &PageRange, password: Option<&str>) -> Result<usize> {
+    Ok(pages.count(PdfFile::open(pdf_path, password)?.page_count()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_lookup_prefers_file_specific_entries() {
+        let mut settings = PdfRenderSettings::default();
+        assert_eq!(settings.password_for(Path::new("scans/a.pdf")), None);
+
+        settings.password = "shared".to_string();
+        settings.file_passwords.insert("a.pdf".to_string(), "by-name".to_string());
+        settings.file_passwords.insert("scans/b.pdf".to_string(), "by-path".to_string());
+        assert_eq!(settings.password_for(Path::new("scans/a.pdf")), Some("by-name"));
+        assert_eq!(settings.password_for(Path::new("scans/b.pdf")), Some("by-path"));
+        assert_eq!(settings.password_for(Path::new("other/b.pdf")), Some("shared"));
+
+        // 密码不会写入保存的配置
+        let saved = serde_json::to_string(&settings).unwrap();
+        assert!(!saved.contains("shared") && !saved.contains("by-name"));
+    }
+}
@@ -0,0 +1,338 @@
+// PDF内嵌图片提取 - 按原始分辨率和编码导出页面中的图片对象，不经过重新渲染
+
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::pdf_converter::PdfFile;
+use image::{DynamicImage, GrayImage, RgbaImage};
+use pdfium_render::prelude::*;
+use std::ffi::{c_int, c_ulong, c_void};
+use std::io::Cursor;
+use std::path::Path;
+
+/// 内嵌图片的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddedImageFormat {
+    /// DCTDecode 图片，原样写出JPEG数据
+    Jpeg,
+    /// JPXDecode 图片，原样写出JPEG 2000数据
+    Jpeg2000,
+    /// JPEG 2000 裸码流（没有JP2文件头）
+    Jpeg2000Codestream,
+    /// 其他编码（Flate、CCITT、JBIG2等）不能独立成文件，解码为原始像素后无损保存为PNG
+    Png,
+}
+
+impl EmbeddedImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            EmbeddedImageFormat::Jpeg => "jpg",
+            EmbeddedImageFormat::Jpeg2000 => "jp2",
+            EmbeddedImageFormat::Jpeg2000Codestream => "j2k",
+            EmbeddedImageFormat::Png => "png",
+        }
+    }
+}
+
+/// 从页面中提取出的一张图片
+#[derive(Debug, Clone)]
+pub struct EmbeddedImage {
+    /// 图片对象在页面中从1开始的序号，表单对象中的图片用 `-` 连接各层序号，如 `3-2`
+    pub object: String,
+    /// 原始像素尺寸
+    pub width: u32,
+    pub height: u32,
+    pub format: EmbeddedImageFormat,
+    /// 可直接写入文件的数据
+    pub data: Vec<u8>,
+}
+
+/// 从已打开的PDF文档中提取内嵌图片
+///
+/// pdfium-render 的高层接口不提供图片对象的原始数据流，这里借用 [`PdfFile`] 的文档句柄直接调用 PDFium 的 C 接口。
+pub struct PdfImageExtractor<'a> {
+    bindings: &'a dyn PdfiumLibraryBindings,
+    path: &'a Path,
+    document: FPDF_DOCUMENT,
+}
+
+impl<'a> PdfImageExtractor<'a> {
+    /// 使用 `file` 已加载的文档，不再重新打开文件
+    pub fn new(file: &'a PdfFile) -> Self {
+        let bindings = file.document().bindings();
+        Self { bindings, path: file.path(), document: bindings.get_handle_from_document(file.document()) }
+    }
+
+    /// 提取第 `page` 页（从1开始）中的所有图片，按对象在页面中的顺序排列
+    ///
+    /// 每个图片对象单独返回提取结果，调用方决定如何处理失败的对象；
+    /// 页面无法加载时返回 [`ConvertError::PdfExtract`]。
+    pub fn page_images(&self, page: usize) -> Result<Vec<Result<EmbeddedImage>>> {
+        let handle = self.bindings.FPDF_LoadPage(self.document, page as c_int - 1);
+        if handle.is_null() {
+            return Err(ConvertError::PdfExtract { path: self.path.to_path_buf(), page, reason: "页面无法加载" });
+        }
+
+        let mut images = Vec::new();
+        for index in 0..self.bindings.FPDFPage_CountObjects(handle) {
+            let object = self.bindings.FPDFPage_GetObject(handle, index);
+            self.collect(object, (index + 1).to_string(), page, &mut images);
+        }
+        self.bindings.FPDF_ClosePage(handle);
+        Ok(images)
+    }
+
+    /// 收集图片对象，递归进入表单对象
+    fn collect(&self, object: FPDF_PAGEOBJECT, label: String, page: usize, images: &mut Vec<Result<EmbeddedImage>>) {
+        match self.bindings.FPDFPageObj_GetType(object) as u32 {
+            FPDF_PAGEOBJ_IMAGE => images.push(self.extract(object, page, label)),
+            FPDF_PAGEOBJ_FORM => {
+                for index in 0..self.bindings.FPDFFormObj_CountObjects(object).max(0) {
+                    let child = self.bindings.FPDFFormObj_GetObject(object, index as c_ulong);
+                    self.collect(child, format!("{}-{}", label, index + 1), page, images);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn extract(&self, object: FPDF_PAGEOBJECT, page: usize, label: String) -> Result<EmbeddedImage> {
+        let error = |reason| ConvertError::PdfExtract { path: self.path.to_path_buf(), page, reason };
+        let (mut width, mut height) = (0, 0);
+        if !self.bindings.is_true(self.bindings.FPDFImageObj_GetImagePixelSize(object, &mut width, &mut height)) {
+            return Err(error("无法读取图片尺寸"));
+        }
+
+        let (format, data) = match passthrough_format(&self.filters(object)) {
+            Some(format) => {
+                let data = self.raw_data(object);
+                if data.is_empty() {
+                    return Err(error("图片数据为空"));
+                }
+                (refine_format(format, &data), data)
+            },
+            None => {
+                let image = self.decoded_image(object).ok_or_else(|| error("图片无法解码"))?;
+                (EmbeddedImageFormat::Png, encode_png(&image)?)
+            },
+        };
+
+        Ok(EmbeddedImage { object: label, width, height, format, data })
+    }
+
+    /// 图片数据流的解码器名称，按应用顺序排列
+    fn filters(&self, object: FPDF_PAGEOBJECT) -> Vec<String> {
+        (0..self.bindings.FPDFImageObj_GetImageFilterCount(object))
+            .map(|index| {
+                let len = self.bindings.FPDFImageObj_GetImageFilter(object, index, std::ptr::null_mut(), 0);
+                let mut buffer = vec![0u8; len as usize];
+                self.bindings.FPDFImageObj_GetImageFilter(object, index, buffer.as_mut_ptr() as *mut c_void, len);
+                String::from_utf8_lossy(&buffer).trim_end_matches('\0').to_string()
+            })
+            .collect()
+    }
+
+    /// 未经解码的图片数据流
+    fn raw_data(&self, object: FPDF_PAGEOBJECT) -> Vec<u8> {
+        let len = self.bindings.FPDFImageObj_GetImageDataRaw(object, std::ptr::null_mut(), 0);
+        let mut data = vec![0u8; len as usize];
+        let written = self.bindings.FPDFImageObj_GetImageDataRaw(object, data.as_mut_ptr() as *mut c_void, len);
+        data.truncate(written.min(len) as usize);
+        data
+    }
+
+    /// 按原始分辨率解码的像素，不应用页面上的变换和遮罩
+    fn decoded_image(&self, object: FPDF_PAGEOBJECT) -> Option<DynamicImage> {
+        let bitmap = self.bindings.FPDFImageObj_GetBitmap(object);
+        if bitmap.is_null() {
+            return None;
+        }
+        let image = bitmap_to_image(
+            self.bindings.FPDFBitmap_GetFormat(bitmap),
+            self.bindings.FPDFBitmap_GetWidth(bitmap) as usize,
+            self.bindings.FPDFBitmap_GetHeight(bitmap) as usize,
+            self.bindings.FPDFBitmap_GetStride(bitmap) as usize,
+            self.bindings.FPDFBitmap_GetBuffer_as_slice(bitmap),
+        );
+        self.bindings.FPDFBitmap_Destroy(bitmap);
+        image
+    }
+}
+
+/// 只有单一 DCT/JPX 编码的数据流本身就是完整的图片文件，可以原样写出
+fn passthrough_format(filters: &[String]) -> Option<EmbeddedImageFormat> {
+    match filters {
+        [filter] if filter == "DCTDecode" => Some(EmbeddedImageFormat::Jpeg),
+        [filter] if filter == "JPXDecode" => Some(EmbeddedImageFormat::Jpeg2000),
+        _ => None,
+    }
+}
+
+/// JP2 文件以签名框开头，否则是裸码流
+fn refine_format(format: EmbeddedImageFormat, data: &[u8]) -> EmbeddedImageFormat {
+    match format {
+        EmbeddedImageFormat::Jpeg2000 if !data.starts_with(b"\0\0\0\x0CjP  ") => EmbeddedImageFormat::Jpeg2000Codestream,
+        format => format,
+    }
+}
+
+/// 把 PDFium 位图转换为图片，`buffer` 每行占 `stride` 字节
+///
+/// PDFium 位图格式：1 灰度，2 BGR，3 BGRx，4 BGRA
+fn bitmap_to_image(format: c_int, width: usize, height: usize, stride: usize, buffer: &[u8]) -> Option<DynamicImage> {
+    let rows = buffer.chunks(stride.max(1)).take(height);
+    match format {
+        1 => GrayImage::from_raw(width as u32, height as u32, rows.flat_map(|row| &row[..width]).copied().collect())
+            .map(DynamicImage::ImageLuma8),
+        2..=4 => {
+            let channels = if format == 2 { 3 } else { 4 };
+            let pixels = rows
+                .flat_map(|row| row[..width * channels].chunks_exact(channels))
+                .flat_map(|bgr| [bgr[2], bgr[1], bgr[0], if format == 4 { bgr[3] } else { 255 }])
+                .collect();
+            RgbaImage::from_raw(width as u32, height as u32, pixels).map(DynamicImage::ImageRgba8)
+        },
+        _ => None,
+    }
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut data), image::ImageFormat::Png)
+        .map_err(|e| ConvertError::Encode { format: "PNG", source: Box::new(e) })?;
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::converter::naming::{NameContext, NamingSettings};
+
+    fn filters(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_passthrough_formats() {
+        assert_eq!(passthrough_format(&filters(&["DCTDecode"])), Some(EmbeddedImageFormat::Jpeg));
+        assert_eq!(passthrough_format(&filters(&["JPXDecode"])), Some(EmbeddedImageFormat::Jpeg2000));
+        // 多重编码或其他编码的数据流需要解码后保存
+        assert_eq!(passthrough_format(&filters(&["FlateDecode", "DCTDecode"])), None);
+        assert_eq!(passthrough_format(&filters(&["FlateDecode"])), None);
+        assert_eq!(passthrough_format(&[]), None);
+
+        let jp2 = b"\0\0\0\x0CjP  \r\n\x87\n";
+        let codestream = b"\xFF\x4F\xFF\x51";
+        assert_eq!(refine_format(EmbeddedImageFormat::Jpeg2000, jp2), EmbeddedImageFormat::Jpeg2000);
+        assert_eq!(refine_format(EmbeddedImageFormat::Jpeg2000, codestream), EmbeddedImageFormat::Jpeg2000Codestream);
+        assert_eq!(refine_format(EmbeddedImageFormat::Jpeg, codestream), EmbeddedImageFormat::Jpeg);
+    }
+
+    #[test]
+    fn test_decoded_bitmap_saved_as_png() {
+        // 2x1 BGR 位图，每行末尾有2字节对齐填充
+        let buffer = [0, 0, 255, 255, 0, 0, 9, 9];
+        let image = bitmap_to_image(2, 2, 1, 8, &buffer).unwrap();
+        assert_eq!(image.to_rgba8().into_raw(), vec![255, 0, 0, 255, 0, 0, 255, 255]);
+        assert!(bitmap_to_image(7, 2, 1, 8, &buffer).is_none());
+
+        let data = encode_png(&image).unwrap();
+        assert_eq!(image::guess_format(&data).unwrap(), image::ImageFormat::Png);
+        assert_eq!(image::load_from_memory(&data).unwrap().to_rgba8(), image.to_rgba8());
+
+        // 文件名包含页码和对象序号，表单中的图片带上各层序号
+        let naming = NamingSettings::default();
+        let name = |format: EmbeddedImageFormat, object: &str| {
+            let context = NameContext::new(Path::new("docs/scan.pdf"), 1, format.extension()).with_page(4);
+            naming.embedded_image_name(&context, object)
+        };
+        assert_eq!(name(EmbeddedImageFormat::Png, "2"), "scan_page_004_img_2.png");
+        assert_eq!(name(EmbeddedImageFormat::Jpeg, "3-1"), "scan_page_004_img_3-1.jpg");
+    }
+
+    /// 一页包含一张 DCTDecode 图片和一张未压缩 RGB 图片的PDF
+    fn sample_pdf(jpeg: &[u8]) -> Vec<u8> {
+        let content = b"q 2 0 0 2 0 0 cm /Im1 Do Q q 2 0 0 2 4 0 cm /Im2 Do Q";
+        let rgb = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 8 8] /Contents 4 0 R \
+              /Resources << /XObject << /Im1 5 0 R /Im2 6 0 R >> >> >>"
+                .to_vec(),
+        ];
+        let stream = |dict: String, data: &[u8]| {
+            let mut object = format!("{} /Length {} >>\nstream\n", dict, data.len()).into_bytes();
+            object.extend_from_slice(data);
+            object.extend_from_slice(b"\nendstream");
+            object
+        };
+        objects.push(stream("<<".to_string(), content));
+        objects.push(stream(
+            "<< /Type /XObject /Subtype /Image /Width 2 /Height 2 /ColorSpace /DeviceRGB \
+             /BitsPerComponent 8 /Filter /DCTDecode"
+                .to_string(),
+            jpeg,
+        ));
+        objects.push(stream(
+            "<< /Type /XObject /Subtype /Image /Width 2 /Height 2 /ColorSpace /DeviceRGB /BitsPerComponent 8"
+                .to_string(),
+            &rgb,
+        ));
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::new();
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            pdf.extend_from_slice(object);
+            pdf.extend_from_slice(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+        for offset in offsets {
+            pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        pdf.extend_from_slice(
+            format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).as_bytes(),
+        );
+        pdf
+    }
+
+    #[test]
+    fn test_extract_page_images() {
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgb8(image::RgbImage::from_pixel(2, 2, image::Rgb([200, 100, 50])))
+            .write_to(&mut Cursor::new(&mut jpeg), image::ImageFormat::Jpeg)
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("pdf_images_{}.pdf", std::process::id()));
+        std::fs::write(&path, sample_pdf(&jpeg)).unwrap();
+        let file = match PdfFile::open(&path, None) {
+            Ok(file) => file,
+            // 没有 PDFium 动态库的环境无法运行该测试
+            Err(ConvertError::PdfiumUnavailable { .. }) => return,
+            Err(e) => panic!("{}", e.chain()),
+        };
+
+        let images: Vec<_> = PdfImageExtractor::new(&file)
+            .page_images(1)
+            .unwrap()
+            .into_iter()
+            .map(|image| image.unwrap())
+            .collect();
+        assert_eq!(images.len(), 2);
+
+        // DCT 图片原样写出，不重新编码
+        assert_eq!((images[0].object.as_str(), images[0].format), ("1", EmbeddedImageFormat::Jpeg));
+        assert_eq!(images[0].data, jpeg);
+
+        assert_eq!((images[1].object.as_str(), images[1].format), ("2", EmbeddedImageFormat::Png));
+        assert_eq!((images[1].width, images[1].height), (2, 2));
+        let decoded = image::load_from_memory(&images[1].data).unwrap().to_rgb8();
+        assert_eq!(decoded.get_pixel(0, 0).0, [255, 0, 0]);
+
+        assert!(matches!(PdfImageExtractor::new(&file).page_images(2), Err(ConvertError::PdfExtract { page: 2, .. })));
+        drop(file);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,185 @@
+// PDF文本提取 - 按页或按文档输出UTF-8文本，可选输出带单词边界框的JSON
+
+use crate::converter::cancel::CancellationToken;
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::naming::{NameContext, NamingSettings};
+use crate::converter::pdf_converter::PdfFile;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// 文本输出方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum TextOutput {
+    /// 不提取文本
+    #[default]
+    Off,
+    /// 每页一个文件，按PDF页面模板命名
+    PerPage,
+    /// 每个文档一个文件，页面之间用换页符分隔
+    PerDocument,
+}
+
+impl TextOutput {
+    /// 获取所有可用输出方式
+    pub fn all_outputs() -> Vec<(Self, &'static str)> {
+        vec![
+            (TextOutput::Off, "不提取"),
+            (TextOutput::PerPage, "每页一个文件"),
+            (TextOutput::PerDocument, "每个文档一个文件"),
+        ]
+    }
+}
+
+/// PDF文本提取设置
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PdfTextSettings {
+    pub output: TextOutput,
+    /// 同时输出包含单词边界框的JSON文件
+    pub word_boxes: bool,
+}
+
+/// 一页的文本
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageText {
+    /// 从1开始的页码
+    pub page: usize,
+    /// 页面尺寸（PDF点）
+    pub width: f32,
+    pub height: f32,
+    pub text: String,
+    /// 未要求边界框时为空
+    pub words: Vec<Word>,
+}
+
+/// 单词及其边界框
+///
+/// 坐标单位为PDF点（1/72英寸），原点在页面左下角，`bbox` 依次为左、下、右、上。
+/// 单词按空白字符切分，没有空格的中文按连续文字整体输出。
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Word {
+    pub text: String,
+    pub bbox: [f32; 4],
+}
+
+impl Word {
+    /// 追加一个字符并扩大边界框
+    fn push(&mut self, c: char, bbox: [f32; 4]) {
+        self.text.push(c);
+        self.bbox = [
+            self.bbox[0].min(bbox[0]),
+            self.bbox[1].min(bbox[1]),
+            self.bbox[2].max(bbox[2]),
+            self.bbox[3].max(bbox[3]),
+        ];
+    }
+}
+
+/// 按空白字符把页面文本切分为单词
+pub(crate) fn words(text: &PdfPageText) -> Vec<Word> {
+    let mut words = Vec::new();
+    let mut current: Option<Word> = None;
+    for char in text.chars().iter() {
+        let Some(c) = char.unicode_char().filter(|c| !c.is_whitespace() && !c.is_control()) else {
+            words.extend(current.take());
+            continue;
+        };
+        // 没有字形的字符（如部分生成的连字符）不影响边界框
+        let Ok(bounds) = char.tight_bounds() else {
+            continue;
+        };
+        let bbox = [bounds.left().value, bounds.bottom().value, bounds.right().value, bounds.top().value];
+        match current.as_mut() {
+            Some(word) => word.push(c, bbox),
+            None => current = Some(Word { text: c.to_string(), bbox }),
+        }
+    }
+    words.extend(current);
+    words
+}
+
+/// 整个文档的文本，页面之间用换页符分隔（与 pdftotext 一致）
+fn document_text(pages: &[PageText]) -> String {
+    pages.iter().map(|page| page.text.as_str()).collect::<Vec<_>>().join("\n\u{c}")
+}
+
+/// 按文档写出的JSON
+#[derive(Serialize)]
+struct DocumentText<'a> {
+    pages: &'a [PageText],
+}
+
+/// 按设置写出 `document` 中 `pages` 的文本
+///
+/// 文件名按命名模板生成：逐页输出使用PDF页面模板，按文档输出使用图片模板，扩展名为 `txt` 和 `json`。
+/// `resolve` 把文件名映射为输出路径，返回 None 表示按冲突策略跳过。返回写入的文件数。
+pub fn write_text(
+    document: &PdfFile,
+    pages: &[usize],
+    settings: &PdfTextSettings,
+    naming: &NamingSettings,
+    name: &NameContext,
+    cancel: &CancellationToken,
+    mut resolve: impl FnMut(String) -> Result<Option<PathBuf>>,
+) -> Result<usize> {
+    if settings.output == TextOutput::Off {
+        return Ok(0);
+    }
+    let mut written = 0;
+    let mut write = |file_name: String, contents: &[u8]| -> Result<()> {
+        if let Some(path) = resolve(file_name)? {
+            std::fs::write(&path, contents).map_err(|e| ConvertError::io(&path, e))?;
+            written += 1;
+        }
+        Ok(())
+    };
+    let file_name = |page: Option<usize>, format| {
+        naming.file_name(&NameContext { page, format, ..name.clone() })
+    };
+
+    let mut document_pages = Vec::new();
+    for &page in pages {
+        cancel.check()?;
+        let text = document.page_text(page, settings.word_boxes)?;
+        if settings.output == TextOutput::PerPage {
+            write(file_name(Some(page), "txt"), text.text.as_bytes())?;
+            if settings.word_boxes {
+                write(file_name(Some(page), "json"), &to_json(&text)?)?;
+            }
+        } else {
+            document_pages.push(text);
+        }
+    }
+
+    if settings.output == TextOutput::PerDocument && !document_pages.is_empty() {
+        write(file_name(None, "txt"), document_text(&document_pages).as_bytes())?;
+        if settings.word_boxes {
+            write(file_name(None, "json"), &to_json(&DocumentText { pages: &document_pages })?)?;
+        }
+    }
+    Ok(written)
+}
+
+fn to_json(value: &impl Serialize) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(value).map_err(|e| ConvertError::Encode { format: "JSON", source: Box::new(e) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_word_bounds_and_document_text() {
+        let mut word = Word { text: "a".to_string(), bbox: [10.0, 700.0, 15.0, 712.0] };
+        word.push('b', [15.0, 698.0, 21.0, 710.0]);
+        assert_eq!(word.text, "ab");
+        assert_eq!(word.bbox, [10.0, 698.0, 21.0, 712.0]);
+
+        let page = |page, text: &str| PageText { page, width: 612.0, height: 792.0, text: text.to_string(), words: Vec::new() };
+        assert_eq!(document_text(&[page(1, "first"), page(2, "second")]), "first\n\u{c}second");
+
+        let json = serde_json::to_string(&DocumentText { pages: &[PageText { words: vec![word], ..page(3, "ab") }] }).unwrap();
+        assert!(json.contains(r#""words":[{"text":"ab","bbox":[10.0,698.0,21.0,712.0]}]"#), "{}", json);
+    }
+}
@@ -0,0 +1,327 @@
+// PDF转图片 - 渲染页面或提取内嵌图片，界面和命令行共用
+
+use crate::converter::cancel::CancellationToken;
+use crate::converter::discovery::{self, Accept, Discovery};
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::batch_processor::BatchSummary;
+use crate::converter::image_converter;
+use crate::converter::naming::NameContext;
+use crate::converter::output_guard::{OutputGuard, OutputSettings};
+use crate::converter::pdf_converter::{PdfFile, PdfOutput};
+use crate::converter::pdf_images::PdfImageExtractor;
+use crate::converter::pdf_text::{self, TextOutput};
+use crate::converter::progress::{ProgressEvent, ProgressSink};
+use crate::utils::config::{AppConfig, ProcessingMode};
+use rayon::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// PDF转图片处理器
+pub struct PdfToImageConverter;
+
+/// 一次运行期间共享的进度状态，进度按页计数
+struct PageProgress<'a> {
+    progress: &'a dyn ProgressSink,
+    processed: AtomicUsize,
+    failed: AtomicUsize,
+    total: usize,
+}
+
+impl PageProgress<'_> {
+    fn page_done(&self, path: &Path, page: usize) {
+        let processed = self.processed.fetch_add(1, Ordering::SeqCst) + 1;
+        self.progress.on_event(ProgressEvent::FileDone {
+            path: path.to_path_buf(),
+            page: Some(page),
+            processed,
+            failed: self.failed.load(Ordering::SeqCst),
+            total: self.total,
+        });
+    }
+
+    fn page_failed(&self, path: &Path, page: usize, error: &ConvertError) {
+        eprintln!("⚠️  第 {} 页处理失败: {}", page, error.chain());
+        self.report_failed(path, Some(page), 1, error);
+    }
+
+    /// 整个文件无法打开，`pages` 为该文件计入总数的页数
+    fn file_failed(&self, path: &Path, pages: usize, error: &ConvertError) {
+        eprintln!("❌ 无法处理 {}: {}", path.display(), error.chain());
+        self.report_failed(path, None, pages, error);
+    }
+
+    fn report_failed(&self, path: &Path, page: Option<usize>, tasks: usize, error: &ConvertError) {
+        let failed = self.failed.fetch_add(tasks, Ordering::SeqCst) + tasks;
+        self.progress.on_event(ProgressEvent::FileFailed {
+            path: path.to_path_buf(),
+            page,
+            error: error.chain(),
+            processed: self.processed.load(Ordering::SeqCst),
+            failed,
+            total: self.total,
+        });
+    }
+}
+
+impl PdfToImageConverter {
+    /// 按应用配置处理单个PDF或文件夹中的所有PDF，进度通过 `progress` 按页报告
+    ///
+    /// 多个PDF时每个文件输出到以文件名命名的子文件夹。渲染、提取、编码或写入失败的页面
+    /// 计入失败统计后继续处理其他页面；文件夹模式下无法打开的PDF整个计为失败。`cancel` 被触发后不再开始新的页面，已完成的页面保留。
+    pub fn process_files(
+        input_path: &Path,
+        output_path: &Path,
+        config: &AppConfig,
+        progress: &dyn ProgressSink,
+        cancel: &CancellationToken,
+    ) -> Result<BatchSummary> {
+        let pdf_pages = config.pdf_render.pages()?;
+
+        // 根据处理模式确定要处理的PDF文件列表
+        let pdf_files: Vec<PathBuf> = match config.default_processing_mode {
+            ProcessingMode::SingleFile => {
+                // 单文件模式：按内容检查是否为PDF文件
+                discovery::check_file(input_path, Accept::Pdfs)?;
+                vec![input_path.to_path_buf()]
+            },
+            ProcessingMode::Folder => {
+                // 文件夹模式：遍历文件夹中的所有PDF文件
+                if !input_path.is_dir() {
+                    return Err(ConvertError::InvalidInput { path: input_path.to_path_buf(), reason: "不是文件夹" });
+                }
+
+                let pdf_files = Discovery::new(&config.discovery, config.folder_settings.walk_depth())?
+                    .files(input_path, Accept::Pdfs)?;
+
+                if pdf_files.is_empty() {
+                    return Err(ConvertError::NoPdfs { path: input_path.to_path_buf() });
+                }
+
+                pdf_files
+            }
+        };
+
+        println!("🔄 开始PDF转图片转换，找到 {} 个PDF文件", pdf_files.len());
+
+        // 预扫描时打开的文档在处理时直接使用，每个PDF只加载一次
+        let documents: Vec<Result<PdfFile>> = pdf_files.iter()
+            .map(|pdf_file| PdfFile::open(pdf_file, config.pdf_render.password_for(pdf_file)))
+            .collect();
+
+        // 计算每个文件选中的页数用于进度条，无法打开的文件按1页计入，处理时计为失败
+        let pages_per_file: Vec<usize> = pdf_files.iter().zip(&documents).map(|(pdf_file, document)| {
+            match document {
+                Ok(document) => {
+                    let count = pdf_pages.count(document.page_count());
+                    println!("📄 PDF文件 {} 选中 {} 页", pdf_file.display(), count);
+                    count
+                },
+                Err(e) => {
+                    eprintln!("⚠️  无法获取PDF页数 {}: {}，默认为1页", pdf_file.display(), e);
+                    1
+                }
+            }
+        }).collect();
+        let total_pages: usize = pages_per_file.iter().sum();
+        progress.on_event(ProgressEvent::Started { total: total_pages });
+
+        // 确保输出目录存在
+        std::fs::create_dir_all(output_path).map_err(|e| ConvertError::io(output_path, e))?;
+
+        // 页面图片总是写到输出目录，替换原文件模式不适用于PDF
+        let collision_only = OutputSettings { replace_originals: false, ..config.output_settings };
+        let guard = OutputGuard::new(collision_only, false, &pdf_files);
+        let pages = PageProgress { progress, processed: AtomicUsize::new(0), failed: AtomicUsize::new(0), total: total_pages };
+
+        let render_config = config.pdf_render.render_config(config.advanced_settings.pdf_render_dpi);
+        let encode_options = config.encode_options();
+        let num_cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+
+        // 提取内嵌图片时输出的图片数与页数无关，单独计数
+        let mut total_images = 0;
+        for (file_index, (pdf_file, document)) in pdf_files.iter().zip(documents).enumerate() {
+            println!("📄 处理第 {} 个PDF: {}", file_index + 1, pdf_file.display());
+
+            // 为每个PDF文件创建子文件夹（如果是批量处理），文件夹模式下按设置重建源文件的相对子目录
+            let file_output_dir = if pdf_files.len() > 1 {
+                let file_stem = pdf_file.file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("unknown");
+                config.folder_settings.output_dir_for(input_path, pdf_file, output_path).join(file_stem)
+            } else {
+                output_path.to_path_buf()
+            };
+
+            std::fs::create_dir_all(&file_output_dir).map_err(|e| ConvertError::io(&file_output_dir, e))?;
+
+            // 文本与图片写到同一目录
+            let write_text = |document: &PdfFile| -> Result<()> {
+                let pages = pdf_pages.pages(document.page_count());
+                let name = NameContext::new(pdf_file, file_index + 1, "txt");
+                let written = pdf_text::write_text(document, &pages, &config.pdf_text, &config.naming, &name, cancel, |file_name| {
+                    guard.resolve(pdf_file, file_output_dir.join(file_name))
+                });
+                match written {
+                    Ok(count) => println!("📝 输出 {} 个文本文件", count),
+                    Err(ConvertError::Cancelled) => {},
+                    Err(e) => return Err(e),
+                }
+                Ok(())
+            };
+            let extract_text = config.pdf_text.output != TextOutput::Off;
+
+            // 文件夹模式下无法打开的PDF（密码缺失或错误、文件损坏）计为失败，继续处理下一个文件；
+            // 单文件模式返回错误，界面据此提示输入密码
+            let open_failed = |e: ConvertError| -> Result<()> {
+                match config.default_processing_mode {
+                    ProcessingMode::Folder => {
+                        pages.file_failed(pdf_file, pages_per_file[file_index], &e);
+                        Ok(())
+                    },
+                    ProcessingMode::SingleFile => Err(e),
+                }
+            };
+            let document = match document {
+                Ok(document) => document,
+                Err(e) => {
+                    open_failed(e)?;
+                    continue;
+                },
+            };
+
+            // 提取内嵌图片：原样写出图片数据，不经过渲染、缩放和重新编码
+            if config.pdf_render.output == PdfOutput::EmbeddedImages {
+                let extractor = PdfImageExtractor::new(&document);
+                let mut file_images = 0;
+                for page in pdf_pages.pages(document.page_count()) {
+                    if cancel.is_cancelled() {
+                        break;
+                    }
+                    // 单个图片对象提取失败时仍写出其余图片，但该页计为失败
+                    let extracted = extractor.page_images(page).and_then(|images| {
+                        let mut failure = None;
+                        for image in images {
+                            let image = match image {
+                                Ok(image) => image,
+                                Err(e) => {
+                                    failure.get_or_insert(e);
+                                    continue;
+                                },
+                            };
+                            let name = NameContext::new(pdf_file, file_index + 1, image.format.extension())
+                                .with_page(page)
+                                .with_dimensions(image.width, image.height);
+                            let output_file = file_output_dir.join(config.naming.embedded_image_name(&name, &image.object));
+                            match guard.resolve(pdf_file, output_file)? {
+                                Some(output_file) => {
+                                    std::fs::write(&output_file, &image.data).map_err(|e| ConvertError::io(&output_file, e))?;
+                                    file_images += 1;
+                                },
+                                None => println!("⏭️  输出文件已存在，跳过第 {} 页图片 {}", page, image.object),
+                            }
+                        }
+                        failure.map_or(Ok(()), Err)
+                    });
+                    match extracted {
+                        Ok(()) => pages.page_done(pdf_file, page),
+                        Err(e) => pages.page_failed(pdf_file, page, &e),
+                    }
+                }
+
+                println!("✅ 提取 {} 张内嵌图片", file_images);
+                if extract_text {
+                    write_text(&document)?;
+                }
+                total_images += file_images;
+                if cancel.is_cancelled() {
+                    break;
+                }
+                continue;
+            }
+
+            // 调整尺寸后按配置的输出格式和质量编码保存
+            let save_page = |page: usize, image: image::DynamicImage| -> Result<()> {
+                let image = config.resize_settings.apply(&image)?;
+                let name = NameContext::new(pdf_file, file_index + 1, config.default_output_format.extension())
+                    .with_page(page)
+                    .with_dimensions(image.width(), image.height());
+                match guard.resolve(pdf_file, file_output_dir.join(config.naming.file_name(&name)))? {
+                    Some(output_file) => image_converter::compress_and_save_with_options(
+                        &image,
+                        &output_file,
+                        config.default_target_size,
+                        config.default_output_format,
+                        &encode_options,
+                    ),
+                    None => {
+                        println!("⏭️  输出文件已存在，跳过第 {} 页", page);
+                        Ok(())
+                    },
+                }
+            };
+
+            // 渲染线程逐页产出，页面在 rayon 线程中并行编码后立即释放；
+            // 通道容量限制同时保存在内存中的页面数
+            let rendered = std::thread::scope(|scope| -> Result<usize> {
+                let (page_sender, page_receiver) = std::sync::mpsc::sync_channel::<(usize, image::DynamicImage)>(num_cores);
+                let renderer = scope.spawn(|| {
+                    let pages = &pages;
+                    let selected = pdf_pages.pages(document.page_count());
+                    document.render_pages(&render_config, &selected, cancel, move |page, image| match image {
+                        // 接收端在渲染结束前不会退出，发送失败说明编码线程已不存在
+                        Ok(image) => page_sender.send((page, image)).map_err(|_| ConvertError::Cancelled),
+                        Err(e) => {
+                            pages.page_failed(pdf_file, page, &e);
+                            Ok(())
+                        },
+                    })
+                });
+
+                page_receiver.into_iter().par_bridge().for_each(|(page, image)| {
+                    if cancel.is_cancelled() {
+                        return;
+                    }
+
+                    match save_page(page, image) {
+                        Ok(()) => pages.page_done(pdf_file, page),
+                        Err(e) => pages.page_failed(pdf_file, page, &e),
+                    }
+                });
+
+                renderer.join().expect("PDF渲染线程不应panic")
+            });
+
+            // 取消后保留已完成的页面，不再处理后续文件；渲染失败的页面已逐页计入失败
+            match rendered {
+                Ok(count) => println!("✅ 成功渲染 {} 页", count),
+                Err(ConvertError::Cancelled | ConvertError::NoRenderablePages { .. }) => {},
+                Err(e) => return Err(e),
+            }
+            if extract_text {
+                write_text(&document)?;
+            }
+
+            if cancel.is_cancelled() {
+                break;
+            }
+        }
+
+        let summary = BatchSummary {
+            processed: pages.processed.load(Ordering::SeqCst),
+            failed: pages.failed.load(Ordering::SeqCst),
+            total: total_pages,
+            cancelled: cancel.is_cancelled(),
+        };
+        match config.pdf_render.output {
+            PdfOutput::EmbeddedImages => println!("🎉 PDF转图片完成! 共处理 {} 页，提取 {} 张图片", summary.processed, total_images),
+            PdfOutput::RenderPages => println!("🎉 PDF转图片完成! 共处理 {} 页", summary.processed),
+        }
+        progress.on_event(ProgressEvent::Finished {
+            processed: summary.processed,
+            failed: summary.failed,
+            total: summary.total,
+            cancelled: summary.cancelled,
+        });
+        Ok(summary)
+    }
+}
@@ -0,0 +1,286 @@
+// PNG调色板量化 - 类似 pngquant 的有损PNG压缩
+//
+// 将图像归约为不超过256色的自适应调色板（支持透明度），再以索引色PNG编码。
+// 颜色数较少的图像（截图、界面素材）直接使用精确调色板，不损失任何像素。
+
+use crate::converter::error::{ConvertError, Result};
+use color_quant::NeuQuant;
+use image::{DynamicImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// NeuQuant 采样因子：1 最精确，30 最快，10 是常用折中
+const NEUQUANT_SAMPLE_FACTOR: i32 = 10;
+
+/// 调色板量化设置
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PngQuantOptions {
+    /// 是否启用调色板量化（关闭时只通过缩放达到目标大小）
+    pub enabled: bool,
+    /// 调色板最大颜色数 (2-256)
+    pub max_colors: u16,
+    /// 逐级减少颜色时允许的最少颜色数
+    pub min_colors: u16,
+    /// 是否使用 Floyd-Steinberg 抖动
+    pub dithering: bool,
+}
+
+impl Default for PngQuantOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_colors: 256,
+            min_colors: 16,
+            dithering: true,
+        }
+    }
+}
+
+impl PngQuantOptions {
+    /// 依次尝试的颜色数：从最大颜色数开始逐级减半，直到最少颜色数
+    pub fn color_steps(&self) -> Vec<u16> {
+        let max_colors = self.max_colors.clamp(2, 256);
+        let min_colors = self.min_colors.clamp(2, max_colors);
+
+        let mut steps = vec![max_colors];
+        let mut colors = max_colors / 2;
+        while colors >= min_colors {
+            steps.push(colors);
+            colors /= 2;
+        }
+        steps
+    }
+}
+
+/// 量化后的索引色图像
+#[derive(Debug, Clone)]
+pub struct QuantizedImage {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA调色板
+    pub palette: Vec<[u8; 4]>,
+    /// 每个像素对应的调色板索引
+    pub indices: Vec<u8>,
+}
+
+/// 将图像量化为不超过 `max_colors` 色的调色板图像
+///
+/// 图像本身颜色数不超过上限时使用精确调色板，否则用 NeuQuant 生成自适应调色板。
+pub fn quantize(image: &DynamicImage, max_colors: u16, dithering: bool) -> QuantizedImage {
+    let rgba = image.to_rgba8();
+    let max_colors = max_colors.clamp(2, 256) as usize;
+
+    if let Some(exact) = exact_palette(&rgba, max_colors) {
+        return exact;
+    }
+
+    let quantizer = NeuQuant::new(NEUQUANT_SAMPLE_FACTOR, max_colors, rgba.as_raw());
+    let palette: Vec<[u8; 4]> = quantizer
+        .color_map_rgba()
+        .chunks_exact(4)
+        .map(|c| [c[0], c[1], c[2], c[3]])
+        .collect();
+
+    let indices = if dithering {
+        map_with_dithering(&rgba, &quantizer, &palette)
+    } else {
+        rgba.pixels().map(|p| quantizer.index_of(&p.0) as u8).collect()
+    };
+
+    QuantizedImage {
+        width: rgba.width(),
+        height: rgba.height(),
+        palette,
+        indices,
+    }
+}
+
+/// 颜色数不超过上限时构建精确调色板
+fn exact_palette(rgba: &RgbaImage, max_colors: usize) -> Option<QuantizedImage> {
+    let mut lookup: HashMap<[u8; 4], u8> = HashMap::new();
+    let mut palette = Vec::new();
+    let mut indices = Vec::with_capacity((rgba.width() * rgba.height()) as usize);
+
+    for pixel in rgba.pixels() {
+        // 完全透明的像素颜色无意义，统一归为一种
+        let key = if pixel.0[3] == 0 { [0, 0, 0, 0] } else { pixel.0 };
+        let index = match lookup.get(&key) {
+            Some(&index) => index,
+            None => {
+                if palette.len() == max_colors {
+                    return None;
+                }
+                let index = palette.len() as u8;
+                lookup.insert(key, index);
+                palette.push(key);
+                index
+            }
+        };
+        indices.push(index);
+    }
+
+    Some(QuantizedImage {
+        width: rgba.width(),
+        height: rgba.height(),
+        palette,
+        indices,
+    })
+}
+
+/// Floyd-Steinberg 抖动映射
+///
+/// 完全透明的像素直接映射且不扩散误差，避免透明区域出现杂点。
+fn map_with_dithering(rgba: &RgbaImage, quantizer: &NeuQuant, palette: &[[u8; 4]]) -> Vec<u8> {
+    let width = rgba.width() as usize;
+    let height = rgba.height() as usize;
+    let mut indices = Vec::with_capacity(width * height);
+
+    // 当前行和下一行的累计误差（每像素4通道），两端各留一个像素避免越界判断
+    let mut current = vec![[0f32; 4]; width + 2];
+    let mut next = vec![[0f32; 4]; width + 2];
+
+    for y in 0..height {
+        for x in 0..width {
+            let source = rgba.get_pixel(x as u32, y as u32).0;
+            if source[3] == 0 {
+                indices.push(quantizer.index_of(&source) as u8);
+                continue;
+            }
+
+            let error = current[x + 1];
+            let mut wanted = [0u8; 4];
+            for c in 0..4 {
+                wanted[c] = (source[c] as f32 + error[c]).round().clamp(0.0, 255.0) as u8;
+            }
+
+            let index = quantizer.index_of(&wanted);
+            indices.push(index as u8);
+
+            let chosen = palette[index];
+            for c in 0..4 {
+                let diff = wanted[c] as f32 - chosen[c] as f32;
+                current[x + 2][c] += diff * 7.0 / 16.0;
+                next[x][c] += diff * 3.0 / 16.0;
+                next[x + 1][c] += diff * 5.0 / 16.0;
+                next[x + 2][c] += diff * 1.0 / 16.0;
+            }
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        next.iter_mut().for_each(|e| *e = [0.0; 4]);
+    }
+
+    indices
+}
+
+/// 以索引色PNG编码，颜色较少时自动使用 1/2/4 位深度
+pub fn encode_indexed_png(quantized: &QuantizedImage) -> Result<Vec<u8>> {
+    let bit_depth = match quantized.palette.len() {
+        0..=2 => png::BitDepth::One,
+        3..=4 => png::BitDepth::Two,
+        5..=16 => png::BitDepth::Four,
+        _ => png::BitDepth::Eight,
+    };
+    let data = pack_indices(quantized, bit_depth as u8);
+
+    let palette: Vec<u8> = quantized.palette.iter().flat_map(|c| [c[0], c[1], c[2]]).collect();
+    // tRNS 只需写到最后一个非不透明颜色为止
+    let trns_len = quantized.palette.iter().rposition(|c| c[3] != 255).map_or(0, |i| i + 1);
+    let trns: Vec<u8> = quantized.palette[..trns_len].iter().map(|c| c[3]).collect();
+
+    let mut output = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut output, quantized.width, quantized.height);
+        encoder.set_color(png::ColorType::Indexed);
+        encoder.set_depth(bit_depth);
+        encoder.set_palette(palette);
+        if !trns.is_empty() {
+            encoder.set_trns(trns);
+        }
+        encoder.set_compression(png::Compression::Best);
+        // 索引色图像使用滤波通常只会变大
+        encoder.set_filter(png::FilterType::NoFilter);
+        encoder.set_adaptive_filter(png::AdaptiveFilterType::NonAdaptive);
+
+        let mut writer = encoder.write_header().map_err(|e| ConvertError::encode("PNG", e))?;
+        writer.write_image_data(&data).map_err(|e| ConvertError::encode("PNG", e))?;
+        writer.finish().map_err(|e| ConvertError::encode("PNG", e))?;
+    }
+
+    Ok(output)
+}
+
+/// 按位深度将索引打包为PNG扫描行（高位在前，每行按字节对齐）
+fn pack_indices(quantized: &QuantizedImage, bits: u8) -> Vec<u8> {
+    if bits == 8 {
+        return quantized.indices.clone();
+    }
+
+    let width = quantized.width as usize;
+    let pixels_per_byte = (8 / bits) as usize;
+    let row_bytes = width.div_ceil(pixels_per_byte);
+    let mut data = vec![0u8; row_bytes * quantized.height as usize];
+
+    for (row, indices) in quantized.indices.chunks_exact(width).enumerate() {
+        let out = &mut data[row * row_bytes..(row + 1) * row_bytes];
+        for (x, &index) in indices.iter().enumerate() {
+            let shift = 8 - bits * (x % pixels_per_byte + 1) as u8;
+            out[x / pixels_per_byte] |= index << shift;
+        }
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient_image(width: u32, height: u32) -> DynamicImage {
+        let img = RgbaImage::from_fn(width, height, |x, y| {
+            let alpha = if x < 10 { 0 } else { 255 };
+            image::Rgba([(x * 255 / width) as u8, (y * 255 / height) as u8, ((x + y) % 256) as u8, alpha])
+        });
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn test_color_steps() {
+        let options = PngQuantOptions::default();
+        assert_eq!(options.color_steps(), vec![256, 128, 64, 32, 16]);
+
+        let options = PngQuantOptions { max_colors: 300, min_colors: 1, ..Default::default() };
+        assert_eq!(*options.color_steps().first().unwrap(), 256);
+        assert_eq!(*options.color_steps().last().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_exact_palette_is_lossless() {
+        let img = RgbaImage::from_fn(33, 7, |x, _| {
+            if x % 3 == 0 { image::Rgba([255, 0, 0, 255]) } else { image::Rgba([0, 0, 255, 128]) }
+        });
+        let image = DynamicImage::ImageRgba8(img.clone());
+
+        let quantized = quantize(&image, 256, true);
+        assert_eq!(quantized.palette.len(), 2);
+
+        let png_data = encode_indexed_png(&quantized).unwrap();
+        let decoded = image::load_from_memory(&png_data).unwrap().to_rgba8();
+        assert_eq!(decoded, img);
+    }
+
+    #[test]
+    fn test_quantized_png_keeps_size_and_alpha() {
+        let image = gradient_image(120, 80);
+        for &dithering in &[false, true] {
+            let quantized = quantize(&image, 64, dithering);
+            assert!(quantized.palette.len() <= 64);
+
+            let png_data = encode_indexed_png(&quantized).unwrap();
+            let decoded = image::load_from_memory(&png_data).unwrap().to_rgba8();
+            assert_eq!(decoded.dimensions(), (120, 80));
+            assert_eq!(decoded.get_pixel(0, 0).0[3], 0);
+            assert_eq!(decoded.get_pixel(100, 40).0[3], 255);
+        }
+    }
+}
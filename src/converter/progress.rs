@@ -0,0 +1,70 @@
+// 进度报告接口 - 库层只依赖这个trait，不依赖界面或异步运行时
+
+use std::path::{Path, PathBuf};
+
+/// 批处理进度事件
+///
+/// 计数均为累计值；PDF按页计数，`page` 为从1开始的页码。
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// 预扫描完成，开始处理
+    Started { total: usize },
+    /// 一个任务处理成功
+    FileDone {
+        path: PathBuf,
+        page: Option<usize>,
+        processed: usize,
+        failed: usize,
+        total: usize,
+    },
+    /// 一个任务处理失败
+    FileFailed {
+        path: PathBuf,
+        page: Option<usize>,
+        error: String,
+        processed: usize,
+        failed: usize,
+        total: usize,
+    },
+    /// 全部任务结束，或取消后已开始的任务结束
+    Finished {
+        processed: usize,
+        failed: usize,
+        total: usize,
+        cancelled: bool,
+    },
+}
+
+/// 任务的显示名称，PDF页面附带页码
+pub fn describe_task(path: &Path, page: Option<usize>) -> String {
+    match page {
+        Some(page) => format!("{} (第 {} 页)", path.to_string_lossy(), page),
+        None => path.to_string_lossy().to_string(),
+    }
+}
+
+/// 进度接收者
+///
+/// 批处理会在多个工作线程中调用 `on_event`，因此要求 `Send + Sync`。
+pub trait ProgressSink: Send + Sync {
+    fn on_event(&self, event: ProgressEvent);
+}
+
+/// 闭包可以直接作为进度接收者使用
+impl<F> ProgressSink for F
+where
+    F: Fn(ProgressEvent) + Send + Sync,
+{
+    fn on_event(&self, event: ProgressEvent) {
+        self(event)
+    }
+}
+
+/// 忽略所有进度事件
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {
+    fn on_event(&self, _event: ProgressEvent) {}
+}
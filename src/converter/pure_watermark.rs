@@ -0,0 +1,203 @@
+// 纯水印处理 - 只添加水印，保持原格式和原画质，界面和命令行共用
+
+use crate::converter::batch_processor::BatchSummary;
+use crate::converter::cancel::CancellationToken;
+use crate::converter::discovery::{self, Accept, Discovery};
+use crate::converter::error::{ConvertError, Result};
+use crate::converter::image_loader;
+use crate::converter::metadata;
+use crate::converter::naming::NameContext;
+use crate::converter::output_guard::OutputGuard;
+use crate::converter::progress::{ProgressEvent, ProgressSink};
+use crate::converter::simple_watermark::SimpleWatermarkProcessor;
+use crate::utils::config::{AppConfig, ProcessingMode};
+use image::DynamicImage;
+use std::path::{Path, PathBuf};
+
+/// 纯水印处理器
+pub struct PureWatermarkProcessor;
+
+impl PureWatermarkProcessor {
+    /// 按应用配置给单个图片或文件夹中的所有图片添加水印，进度通过 `progress` 报告
+    ///
+    /// 单个文件失败只计入统计；没有启用水印或输入无效时返回错误。
+    /// `cancel` 被触发后不再开始新的文件，已完成的结果保留。
+    pub fn process_files(
+        input_path: &Path,
+        output_path: &Path,
+        config: &AppConfig,
+        progress: &dyn ProgressSink,
+        cancel: &CancellationToken,
+    ) -> Result<BatchSummary> {
+        // 检查是否启用了水印
+        if !config.watermark_settings.enable_text_watermark && !config.watermark_settings.enable_image_watermark {
+            return Err(ConvertError::NoWatermark);
+        }
+
+        // 获取要处理的图片文件列表
+        let image_files: Vec<PathBuf> = match config.default_processing_mode {
+            ProcessingMode::SingleFile => {
+                // 单文件模式：按内容检查是否为支持的图片格式
+                discovery::check_file(input_path, Accept::Images)?;
+                vec![input_path.to_path_buf()]
+            },
+            ProcessingMode::Folder => {
+                // 文件夹模式：遍历文件夹中的所有图片文件
+                if !input_path.is_dir() {
+                    return Err(ConvertError::InvalidInput { path: input_path.to_path_buf(), reason: "不是文件夹" });
+                }
+
+                let image_files = Discovery::new(&config.discovery, config.folder_settings.walk_depth())?
+                    .files(input_path, Accept::Images)?;
+
+                if image_files.is_empty() {
+                    return Err(ConvertError::NoImages { path: input_path.to_path_buf() });
+                }
+
+                image_files
+            }
+        };
+
+        println!("💧 开始纯水印处理，找到 {} 个图片文件", image_files.len());
+        let total = image_files.len();
+        progress.on_event(ProgressEvent::Started { total });
+
+        // 确保输出目录存在
+        std::fs::create_dir_all(output_path).map_err(|e| ConvertError::io(output_path, e))?;
+
+        // 替换原文件模式下输出写回源文件所在目录，源文件受保护不会被意外覆盖
+        let guard = OutputGuard::new(config.output_settings, config.advanced_settings.keep_original_files, &image_files);
+        let watermark_processor = SimpleWatermarkProcessor::new();
+        let mut summary = BatchSummary { total, ..Default::default() };
+
+        for (file_index, image_file) in image_files.iter().enumerate() {
+            if cancel.is_cancelled() {
+                break;
+            }
+            println!("🖼️ 处理第 {} 个图片: {}", file_index + 1, image_file.display());
+
+            let result = Self::process_image(image_file, file_index + 1, input_path, output_path, config, &guard, &watermark_processor);
+            match result {
+                Ok(()) => {
+                    summary.processed += 1;
+                    progress.on_event(ProgressEvent::FileDone {
+                        path: image_file.clone(),
+                        page: None,
+                        processed: summary.processed,
+                        failed: summary.failed,
+                        total,
+                    });
+                },
+                Err(e) => {
+                    summary.failed += 1;
+                    progress.on_event(ProgressEvent::FileFailed {
+                        path: image_file.clone(),
+                        page: None,
+                        error: e.chain(),
+                        processed: summary.processed,
+                        failed: summary.failed,
+                        total,
+                    });
+                },
+            }
+        }
+
+        summary.cancelled = cancel.is_cancelled();
+        println!("🎉 纯水印处理完成! 共处理 {} 张图片", summary.processed);
+        progress.on_event(ProgressEvent::Finished {
+            processed: summary.processed,
+            failed: summary.failed,
+            total: summary.total,
+            cancelled: summary.cancelled,
+        });
+        Ok(summary)
+    }
+
+    /// 给单个图片添加水印并保存，`index` 为文件在本批次中从1开始的序号
+    fn process_image(
+        image_file: &Path,
+        index: usize,
+        input_path: &Path,
+        output_path: &Path,
+        config: &AppConfig,
+        guard: &OutputGuard,
+        watermark_processor: &SimpleWatermarkProcessor,
+    ) -> Result<()> {
+        // 加载原始图片
+        let (mut processed_image, source_metadata) = image_loader::load_image_with_metadata(image_file)?;
+
+        // 添加文字水印
+        if config.watermark_settings.enable_text_watermark {
+            let text_watermark = config.watermark_settings.to_text_watermark();
+            processed_image = watermark_processor.add_text_watermark(processed_image, &text_watermark)?;
+        }
+
+        // 添加图片水印
+        if config.watermark_settings.enable_image_watermark && !config.watermark_settings.image_watermark_path.is_empty() {
+            let image_watermark = config.watermark_settings.to_image_watermark();
+            processed_image = watermark_processor.add_image_watermark(processed_image, &image_watermark)?;
+        }
+
+        // 保存处理后的图片（保持原始格式和质量），文件名按命名模板生成、扩展名不变
+        let source_extension = image_file.extension().unwrap_or_default().to_string_lossy().to_string();
+        let name = NameContext::new(image_file, index, &source_extension)
+            .with_dimensions(processed_image.width(), processed_image.height());
+        let file_output_dir = if guard.replaces_originals() {
+            image_file.parent().unwrap_or_else(|| Path::new(".")).to_path_buf()
+        } else {
+            match config.default_processing_mode {
+                ProcessingMode::SingleFile => output_path.to_path_buf(),
+                ProcessingMode::Folder => config.folder_settings.output_dir_for(input_path, image_file, output_path),
+            }
+        };
+        std::fs::create_dir_all(&file_output_dir).map_err(|e| ConvertError::io(&file_output_dir, e))?;
+        let Some(output_file) = guard.resolve(image_file, file_output_dir.join(config.naming.file_name(&name)))? else {
+            println!("⏭️  输出文件已存在，跳过: {}", image_file.display());
+            return Ok(());
+        };
+
+        // 🔧 智能保存：检测文件格式并处理RGBA兼容性
+        let file_extension = output_file.extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let final_image = if file_extension == "jpg" || file_extension == "jpeg" {
+            // JPEG不支持透明度，转换为RGB
+            match processed_image {
+                DynamicImage::ImageRgba8(_) => DynamicImage::ImageRgb8(processed_image.to_rgb8()),
+                _ => processed_image,
+            }
+        } else {
+            processed_image // PNG等其他格式支持RGBA
+        };
+
+        match metadata::Container::from_path(&output_file) {
+            Some(container) => {
+                // 先编码到内存，写入按策略保留的元数据后再保存
+                let format = image::ImageFormat::from_path(&output_file)
+                    .map_err(|_| ConvertError::UnsupportedFormat { path: output_file.clone(), format: file_extension })?;
+                let mut encoded = Vec::new();
+                final_image.write_to(&mut std::io::Cursor::new(&mut encoded), format)
+                    .map_err(|e| save_error(&output_file, e))?;
+                let encoded = metadata::embed(encoded, container, &source_metadata.filtered(config.metadata_policy))?;
+                std::fs::write(&output_file, encoded).map_err(|e| ConvertError::io(&output_file, e))?;
+            },
+            None => {
+                final_image.save(&output_file).map_err(|e| save_error(&output_file, e))?;
+            }
+        }
+
+        guard.finish(image_file, &output_file)?;
+        println!("✨ 已保存水印图片: {}", output_file.display());
+        Ok(())
+    }
+}
+
+/// 保存图片失败：写文件错误保留路径，其余视为编码错误
+fn save_error(path: &Path, error: image::ImageError) -> ConvertError {
+    match error {
+        image::ImageError::IoError(e) => ConvertError::io(path, e),
+        e => ConvertError::encode("图片", e),
+    }
+}
@@ -0,0 +1,183 @@
+// 尺寸调整 - 编码前按配置缩放图像（最大尺寸、指定尺寸、百分比）
+
+use crate::converter::error::Result;
+use crate::converter::simd_optimizer;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// 尺寸调整方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum ResizeMode {
+    /// 保持原始尺寸
+    #[default]
+    Off,
+    /// 限制最大宽高，只缩小不放大，保持宽高比
+    MaxSize,
+    /// 调整到指定宽高，按适应方式处理宽高比
+    Exact,
+    /// 按百分比缩放
+    Percentage,
+}
+
+impl ResizeMode {
+    /// 获取所有可用方式
+    pub fn all_modes() -> Vec<(Self, &'static str)> {
+        vec![
+            (ResizeMode::Off, "原始尺寸"),
+            (ResizeMode::MaxSize, "最大尺寸"),
+            (ResizeMode::Exact, "指定尺寸"),
+            (ResizeMode::Percentage, "百分比"),
+        ]
+    }
+}
+
+/// 指定尺寸时的适应方式
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum FitMode {
+    /// 完整放入目标区域，保持宽高比（结果可能小于目标）
+    #[default]
+    Contain,
+    /// 填满目标区域并居中裁剪，保持宽高比
+    Cover,
+    /// 拉伸到目标尺寸，不保持宽高比
+    Fill,
+}
+
+impl FitMode {
+    /// 获取所有可用方式
+    pub fn all_modes() -> Vec<(Self, &'static str)> {
+        vec![
+            (FitMode::Contain, "适应"),
+            (FitMode::Cover, "填满裁剪"),
+            (FitMode::Fill, "拉伸"),
+        ]
+    }
+}
+
+/// 尺寸调整设置
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ResizeSettings {
+    pub mode: ResizeMode,
+    /// 目标/最大宽度，0 表示不限制（按宽高比推算）
+    pub width: u32,
+    /// 目标/最大高度，0 表示不限制（按宽高比推算）
+    pub height: u32,
+    /// 缩放百分比
+    pub percentage: f32,
+    /// 指定尺寸时的适应方式
+    pub fit: FitMode,
+}
+
+impl Default for ResizeSettings {
+    fn default() -> Self {
+        Self {
+            mode: ResizeMode::Off,
+            width: 1920,
+            height: 1080,
+            percentage: 50.0,
+            fit: FitMode::Contain,
+        }
+    }
+}
+
+impl ResizeSettings {
+    /// 计算输出尺寸，返回 None 表示无需缩放
+    pub fn target_dimensions(&self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (w, h) = (width as f64, height as f64);
+        let scaled = |scale: f64| {
+            (((w * scale).round() as u32).max(1), ((h * scale).round() as u32).max(1))
+        };
+
+        let target = match self.mode {
+            ResizeMode::Off => return None,
+            ResizeMode::MaxSize => {
+                let scale_x = if self.width > 0 { self.width as f64 / w } else { f64::INFINITY };
+                let scale_y = if self.height > 0 { self.height as f64 / h } else { f64::INFINITY };
+                let scale = scale_x.min(scale_y);
+                if scale >= 1.0 {
+                    return None;
+                }
+                scaled(scale)
+            },
+            ResizeMode::Percentage => {
+                if self.percentage <= 0.0 {
+                    return None;
+                }
+                scaled(self.percentage as f64 / 100.0)
+            },
+            ResizeMode::Exact => match (self.width, self.height) {
+                (0, 0) => return None,
+                (target_w, 0) => scaled(target_w as f64 / w),
+                (0, target_h) => scaled(target_h as f64 / h),
+                (target_w, target_h) => match self.fit {
+                    FitMode::Contain => scaled((target_w as f64 / w).min(target_h as f64 / h)),
+                    FitMode::Cover | FitMode::Fill => (target_w, target_h),
+                },
+            },
+        };
+
+        (target != (width, height)).then_some(target)
+    }
+
+    /// 按设置缩放图像，无需缩放时直接借用原图
+    pub fn apply<'a>(&self, image: &'a DynamicImage) -> Result<Cow<'a, DynamicImage>> {
+        let Some((width, height)) = self.target_dimensions(image.width(), image.height()) else {
+            return Ok(Cow::Borrowed(image));
+        };
+
+        let crop_to_fill = self.mode == ResizeMode::Exact
+            && self.fit == FitMode::Cover
+            && self.width > 0
+            && self.height > 0;
+
+        let resized = if crop_to_fill {
+            simd_optimizer::simd_resize_image_cover(image, width, height)?
+        } else {
+            simd_optimizer::simd_resize_image(image, width, height)?
+        };
+        Ok(Cow::Owned(resized))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(mode: ResizeMode, width: u32, height: u32, fit: FitMode) -> ResizeSettings {
+        ResizeSettings { mode, width, height, fit, ..Default::default() }
+    }
+
+    #[test]
+    fn test_target_dimensions() {
+        let off = ResizeSettings::default();
+        assert_eq!(off.target_dimensions(4000, 3000), None);
+
+        let max = settings(ResizeMode::MaxSize, 1920, 1080, FitMode::Contain);
+        assert_eq!(max.target_dimensions(4000, 3000), Some((1440, 1080)));
+        assert_eq!(max.target_dimensions(800, 600), None); // 不放大
+
+        let max_width = settings(ResizeMode::MaxSize, 1000, 0, FitMode::Contain);
+        assert_eq!(max_width.target_dimensions(4000, 3000), Some((1000, 750)));
+
+        let percent = ResizeSettings { mode: ResizeMode::Percentage, percentage: 25.0, ..Default::default() };
+        assert_eq!(percent.target_dimensions(4000, 3000), Some((1000, 750)));
+
+        let contain = settings(ResizeMode::Exact, 500, 500, FitMode::Contain);
+        assert_eq!(contain.target_dimensions(4000, 3000), Some((500, 375)));
+        let cover = settings(ResizeMode::Exact, 500, 500, FitMode::Cover);
+        assert_eq!(cover.target_dimensions(4000, 3000), Some((500, 500)));
+        let fill = settings(ResizeMode::Exact, 500, 200, FitMode::Fill);
+        assert_eq!(fill.target_dimensions(4000, 3000), Some((500, 200)));
+    }
+
+    #[test]
+    fn test_apply_borrows_when_unchanged() {
+        let image = DynamicImage::new_rgb8(64, 32);
+        assert!(matches!(ResizeSettings::default().apply(&image).unwrap(), Cow::Borrowed(_)));
+
+        let cover = settings(ResizeMode::Exact, 16, 16, FitMode::Cover);
+        let resized = cover.apply(&image).unwrap();
+        assert_eq!((resized.width(), resized.height()), (16, 16));
+    }
+}
@@ -1,27 +1,52 @@
 // 智能图像优化器 - 基于2024年最新算法研究
 
-use anyhow::Result;
-use image::{DynamicImage, imageops::FilterType};
+use crate::converter::error::{ConvertError, Result};
+use fast_image_resize::{FilterType, IntoImageView, ResizeAlg, ResizeOptions, Resizer};
+use image::DynamicImage;
+use std::borrow::Cow;
 
-/// 超级智能的图像缩放器 - 根据缩放比例自动选择最优算法
-#[allow(dead_code)]
+/// 超级智能的图像缩放器 - 根据缩放比例自动选择最优算法，使用 fast_image_resize 的SIMD实现
 pub fn simd_resize_image(
     image: &DynamicImage,
     target_width: u32,
     target_height: u32,
 ) -> Result<DynamicImage> {
-    let src_width = image.width();
-    let src_height = image.height();
+    fast_resize(image, target_width, target_height, false)
+}
+
+/// 等比缩放并居中裁剪到目标尺寸（填满目标区域，不变形）
+pub fn simd_resize_image_cover(
+    image: &DynamicImage,
+    target_width: u32,
+    target_height: u32,
+) -> Result<DynamicImage> {
+    fast_resize(image, target_width, target_height, true)
+}
+
+fn fast_resize(
+    image: &DynamicImage,
+    target_width: u32,
+    target_height: u32,
+    crop_to_fill: bool,
+) -> Result<DynamicImage> {
+    let target_width = target_width.max(1);
+    let target_height = target_height.max(1);
+
+    // fast_image_resize 只支持8/16位整数像素，其余格式先转为RGBA8
+    let source = match IntoImageView::pixel_type(image) {
+        Some(_) => Cow::Borrowed(image),
+        None => Cow::Owned(DynamicImage::ImageRgba8(image.to_rgba8())),
+    };
 
     // 计算缩放比例
-    let scale_x = target_width as f32 / src_width as f32;
-    let scale_y = target_height as f32 / src_height as f32;
+    let scale_x = target_width as f32 / source.width() as f32;
+    let scale_y = target_height as f32 / source.height() as f32;
     let avg_scale = (scale_x + scale_y) / 2.0;
 
     // 智能算法选择策略（基于2024研究）
     let filter = if avg_scale > 2.0 {
         // 大幅放大：使用双线性避免过度锐化
-        FilterType::Triangle
+        FilterType::Bilinear
     } else if avg_scale > 0.8 {
         // 轻微缩放：使用Lanczos3获得最佳质量
         FilterType::Lanczos3
@@ -32,12 +57,19 @@ pub fn simd_resize_image(
         // 大幅缩小：使用Gaussian避免混叠
         FilterType::Gaussian
     } else {
-        // 极端缩小：使用最快的算法
-        FilterType::Nearest
+        // 极端缩小：卷积核随比例自动放大，双线性已足够且最快
+        FilterType::Bilinear
     };
 
-    // 使用优化的缩放算法
-    let result = image.resize_exact(target_width, target_height, filter);
+    let mut options = ResizeOptions::new().resize_alg(ResizeAlg::Convolution(filter));
+    if crop_to_fill {
+        options = options.fit_into_destination(None);
+    }
+
+    let mut result = DynamicImage::new(target_width, target_height, source.color());
+    Resizer::new()
+        .resize(source.as_ref(), &mut result, &options)
+        .map_err(|source| ConvertError::Resize { source })?;
     Ok(result)
 }
 
@@ -89,6 +121,18 @@ mod tests {
         assert_eq!(resized.height(), 50);
     }
 
+    #[test]
+    fn test_resize_cover_and_float_images() {
+        let test_image = DynamicImage::new_rgba8(200, 100);
+        let cover = simd_resize_image_cover(&test_image, 50, 50).unwrap();
+        assert_eq!((cover.width(), cover.height()), (50, 50));
+        assert!(cover.color().has_alpha());
+
+        let float_image = DynamicImage::new_rgb32f(40, 40);
+        let resized = simd_resize_image(&float_image, 20, 10).unwrap();
+        assert_eq!((resized.width(), resized.height()), (20, 10));
+    }
+
     #[test]
     fn test_estimate_dimensions() {
         let (w, h) = estimate_optimal_dimensions(1000, 1000, 1000000, 500000);
@@ -1,9 +1,10 @@
 // 简单图片转PDF实现 - 直接使用printpdf
-use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 use std::fs;
 use printpdf::*;
 use image::DynamicImage;
+use crate::converter::discovery::{Accept, Discovery, DiscoverySettings};
+use crate::converter::error::{ConvertError, Result};
 
 /// 简单PDF转换器
 pub struct SimpleImageToPdf;
@@ -18,7 +19,7 @@ impl SimpleImageToPdf {
 
         // 读取图片
         let image = image::open(image_path)
-            .with_context(|| format!("无法读取图片文件: {}", image_path.display()))?;
+            .map_err(|source| ConvertError::Decode { path: image_path.to_path_buf(), source })?;
 
         // 创建PDF文档
         let (doc, page1, layer1) = PdfDocument::new("Image to PDF", Mm(210.0), Mm(297.0), "Layer 1");
@@ -27,8 +28,7 @@ impl SimpleImageToPdf {
         Self::add_image_to_page(&doc, &page1, &layer1, &image)?;
 
         // 保存PDF
-        doc.save(&mut std::io::BufWriter::new(std::fs::File::create(output_path)?))
-            .with_context(|| "保存PDF文件失败")?;
+        Self::save(doc, output_path)?;
 
         println!("✅ 转换完成: {}", output_path.display());
         Ok(())
@@ -100,7 +100,7 @@ impl SimpleImageToPdf {
 
         let image_files = Self::get_image_files(folder_path)?;
         if image_files.is_empty() {
-            anyhow::bail!("文件夹中没有找到图片文件");
+            return Err(ConvertError::NoImages { path: folder_path.to_path_buf() });
         }
 
         println!("📸 找到 {} 张图片", image_files.len());
@@ -133,34 +133,23 @@ impl SimpleImageToPdf {
         }
 
         // 保存PDF
-        doc.save(&mut std::io::BufWriter::new(std::fs::File::create(output_path)?))
-            .with_context(|| "保存PDF文件失败")?;
+        Self::save(doc, output_path)?;
 
         println!("🎉 批量转换完成: {}", output_path.display());
         Ok(())
     }
 
-    /// 获取文件夹中的图片文件
-    fn get_image_files(folder_path: &Path) -> Result<Vec<PathBuf>> {
-        let supported_extensions = ["jpg", "jpeg", "png", "bmp", "tiff", "webp", "gif"];
-        let mut image_files = Vec::new();
-
-        for entry in std::fs::read_dir(folder_path)? {
-            let entry = entry.with_context(|| "遍历文件夹失败")?;
-
-            if entry.file_type()?.is_file() {
-                if let Some(extension) = entry.path().extension() {
-                    let ext_str = extension.to_string_lossy().to_lowercase();
-                    if supported_extensions.contains(&ext_str.as_str()) {
-                        image_files.push(entry.path().to_path_buf());
-                    }
-                }
-            }
-        }
-
-        // 按文件名排序
-        image_files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    /// 保存PDF文档到 `output_path`
+    fn save(doc: PdfDocumentReference, output_path: &Path) -> Result<()> {
+        let file = fs::File::create(output_path).map_err(|e| ConvertError::io(output_path, e))?;
+        doc.save(&mut std::io::BufWriter::new(file))
+            .map_err(|e| ConvertError::PdfWrite { path: output_path.to_path_buf(), source: e.into() })
+    }
 
+    /// 获取文件夹中的图片文件（按内容识别格式，按文件名排序）
+    fn get_image_files(folder_path: &Path) -> Result<Vec<PathBuf>> {
+        let image_files = Discovery::new(&DiscoverySettings::default(), 1)?
+            .files(folder_path, Accept::Images)?;
         Ok(image_files)
     }
 }
\ No newline at end of file
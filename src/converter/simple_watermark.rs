@@ -1,7 +1,9 @@
 // 简化水印处理器 - 无需外部字体依赖
 
-use anyhow::{Context, Result};
+use crate::converter::error::Result;
 use image::{DynamicImage, Rgba, RgbaImage};
+use std::path::Path;
+use crate::converter::image_loader;
 
 /// 水印位置枚举
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -170,8 +172,7 @@ impl SimpleWatermarkProcessor {
         let start = std::time::Instant::now();
 
         // 加载水印图片
-        let watermark = image::open(&config.watermark_path)
-            .with_context(|| format!("无法加载水印图片: {}", config.watermark_path))?;
+        let watermark = image_loader::load_image(Path::new(&config.watermark_path))?;
 
         // 缩放水印
         let scaled_watermark = if config.scale != 1.0 {
@@ -1,8 +1,10 @@
 // 涡轮增压编码器 - 极致性能优化
 
-use anyhow::{Context, Result};
+use crate::converter::error::{ConvertError, Result};
 use image::{DynamicImage, ImageEncoder};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use crate::converter::png_quantizer::{self, PngQuantOptions};
 use std::io::Cursor;
 
 /// 内存池 - 复用大块内存避免分配开销
@@ -42,23 +44,298 @@ impl MemoryPool {
     }
 }
 
+/// 目标大小搜索参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetSearch {
+    /// 允许的最低质量
+    pub min_quality: u8,
+    /// 允许的最高质量（搜索起点）
+    pub max_quality: u8,
+    /// 最低质量仍超出目标时，是否允许缩小尺寸
+    pub allow_downscale: bool,
+    /// 允许缩小到的最小比例 (0.0-1.0]
+    pub min_scale: f32,
+}
+
+impl Default for TargetSearch {
+    fn default() -> Self {
+        Self {
+            min_quality: 10,
+            max_quality: 95,
+            allow_downscale: true,
+            min_scale: 0.1,
+        }
+    }
+}
+
+/// 按目标大小编码的结果
+#[derive(Debug, Clone)]
+pub struct EncodedImage {
+    pub data: Vec<u8>,
+    /// 最终选择的质量
+    pub quality: u8,
+    /// 最终选择的缩放比例（1.0 表示未缩放）
+    pub scale: f32,
+}
+
+/// JPEG编码后端
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum JpegBackend {
+    /// image 库内置编码器（速度快）
+    Standard,
+    /// mozjpeg 编码器（同等质量下体积更小，速度较慢）
+    Mozjpeg,
+}
+
+impl JpegBackend {
+    /// 获取所有可用后端
+    pub fn all_backends() -> Vec<(Self, &'static str)> {
+        vec![
+            (JpegBackend::Standard, "标准"),
+            (JpegBackend::Mozjpeg, "mozjpeg"),
+        ]
+    }
+}
+
+/// JPEG色度子采样
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ChromaSubsampling {
+    /// 4:4:4，不做子采样，色彩最准确
+    Yuv444,
+    /// 4:2:2，水平方向减半
+    Yuv422,
+    /// 4:2:0，水平和垂直方向均减半，体积最小
+    Yuv420,
+}
+
+impl ChromaSubsampling {
+    /// 获取所有可用子采样方式
+    pub fn all_modes() -> Vec<(Self, &'static str)> {
+        vec![
+            (ChromaSubsampling::Yuv444, "4:4:4"),
+            (ChromaSubsampling::Yuv422, "4:2:2"),
+            (ChromaSubsampling::Yuv420, "4:2:0"),
+        ]
+    }
+
+    /// mozjpeg 使用的 Cb/Cr 色度像素尺寸
+    fn pixel_sizes(self) -> (u8, u8) {
+        match self {
+            ChromaSubsampling::Yuv444 => (1, 1),
+            ChromaSubsampling::Yuv422 => (2, 1),
+            ChromaSubsampling::Yuv420 => (2, 2),
+        }
+    }
+}
+
+/// JPEG编码器设置
+///
+/// 除 `backend` 外的选项只对 mozjpeg 后端生效。
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JpegEncoderSettings {
+    /// 编码后端
+    pub backend: JpegBackend,
+    /// 渐进式扫描
+    pub progressive: bool,
+    /// 网格量化（trellis quantization）
+    pub trellis: bool,
+    /// 优化霍夫曼表
+    pub optimize_huffman: bool,
+    /// 色度子采样
+    pub chroma_subsampling: ChromaSubsampling,
+}
+
+impl Default for JpegEncoderSettings {
+    fn default() -> Self {
+        Self {
+            backend: JpegBackend::Standard,
+            progressive: true,
+            trellis: true,
+            optimize_huffman: true,
+            chroma_subsampling: ChromaSubsampling::Yuv420,
+        }
+    }
+}
+
+/// 单次质量搜索的最大编码次数（0-100 的二分搜索最多需要7次）
+const MAX_QUALITY_STEPS: usize = 8;
+/// 缩小尺寸的最大尝试次数
+const MAX_DOWNSCALE_STEPS: usize = 4;
+
 /// 超高性能JPEG编码器
+///
+/// 指定 `target_size_bytes` 时，在 `[10, quality]` 范围内搜索不超过目标的最高质量。
+#[allow(dead_code)]
 pub fn turbo_encode_jpeg(
     image: &DynamicImage,
     quality: u8,
     target_size_bytes: Option<usize>
 ) -> Result<Vec<u8>> {
-    let start = std::time::Instant::now();
+    match target_size_bytes {
+        Some(target) => {
+            let search = TargetSearch {
+                max_quality: quality,
+                min_quality: quality.min(TargetSearch::default().min_quality),
+                ..Default::default()
+            };
+            Ok(turbo_encode_jpeg_to_target(image, target, &search, &JpegEncoderSettings::default())?.data)
+        },
+        None => {
+            // 转换为最优格式
+            let rgb_image = match image {
+                DynamicImage::ImageRgb8(img) => img.clone(),
+                _ => image.to_rgb8(), // 只在需要时转换
+            };
+            encode_jpeg_rgb(&rgb_image, quality, &JpegEncoderSettings::default())
+        }
+    }
+}
 
-    // 转换为最优格式
+/// 按目标大小编码JPEG
+///
+/// 每次尝试都使用 `encoder` 指定的后端编码，保证搜索结果与最终输出一致。
+pub fn turbo_encode_jpeg_to_target(
+    image: &DynamicImage,
+    target_bytes: usize,
+    search: &TargetSearch,
+    encoder: &JpegEncoderSettings,
+) -> Result<EncodedImage> {
+    // 只转换一次RGB，缩放后仍保持RGB8
     let rgb_image = match image {
-        DynamicImage::ImageRgb8(img) => img.clone(),
-        _ => image.to_rgb8(), // 只在需要时转换
+        DynamicImage::ImageRgb8(_) => image.clone(),
+        _ => DynamicImage::ImageRgb8(image.to_rgb8()),
     };
 
+    encode_to_target(&rgb_image, target_bytes, search, "JPEG", |current, quality| {
+        match current {
+            DynamicImage::ImageRgb8(rgb) => encode_jpeg_rgb(rgb, quality, encoder),
+            other => encode_jpeg_rgb(&other.to_rgb8(), quality, encoder),
+        }
+    })
+}
+
+/// 通用的目标大小编码（JPEG、AVIF 等有质量参数的格式共用）
+///
+/// 先对质量做有界二分搜索，找到不超过目标的最高质量；
+/// 若最低质量仍然超出目标且允许缩放，则按体积比例估算缩放后重新搜索。
+pub fn encode_to_target<F>(
+    image: &DynamicImage,
+    target_bytes: usize,
+    search: &TargetSearch,
+    format_name: &'static str,
+    encode: F,
+) -> Result<EncodedImage>
+where
+    F: Fn(&DynamicImage, u8) -> Result<Vec<u8>>,
+{
+    let start = std::time::Instant::now();
+    let min_quality = search.min_quality.clamp(1, 100);
+    let max_quality = search.max_quality.clamp(min_quality, 100);
+
+    let mut scale = 1.0f32;
+    let mut scaled: Option<DynamicImage> = None;
+
+    for attempt in 0..=MAX_DOWNSCALE_STEPS {
+        let current = scaled.as_ref().unwrap_or(image);
+        let smallest_size = match search_quality(current, target_bytes, min_quality, max_quality, &encode)? {
+            QualitySearch::Found { data, quality } => {
+                log::debug!("{}目标编码耗时: {:?}, 质量: {}, 缩放: {:.2}x, 大小: {} bytes (目标 {} bytes)",
+                           format_name, start.elapsed(), quality, scale, data.len(), target_bytes);
+                return Ok(EncodedImage { data, quality, scale });
+            },
+            QualitySearch::TooLarge { smallest_size } => smallest_size,
+        };
+
+        if !search.allow_downscale || attempt == MAX_DOWNSCALE_STEPS {
+            break;
+        }
+
+        // 体积大致与像素数成正比，按面积比例估算新的缩放比例并留出余量
+        let ratio = (target_bytes as f32 / smallest_size as f32).sqrt() * 0.95;
+        let next_scale = scale * ratio.min(0.95);
+        if next_scale < search.min_scale {
+            break;
+        }
+        scale = next_scale;
+
+        // 始终从原图缩放，避免多次重采样累积模糊
+        let new_width = ((image.width() as f32 * scale).round() as u32).max(1);
+        let new_height = ((image.height() as f32 * scale).round() as u32).max(1);
+        scaled = Some(image.resize_exact(new_width, new_height, image::imageops::FilterType::CatmullRom));
+    }
+
+    Err(ConvertError::TargetTooSmall { format: format_name, target_kb: target_bytes / 1024, scale })
+}
+
+/// 质量搜索结果
+enum QualitySearch {
+    /// 找到不超过目标的最高质量
+    Found { data: Vec<u8>, quality: u8 },
+    /// 最低质量仍超出目标，附带最低质量下的大小供缩放估算使用
+    TooLarge { smallest_size: usize },
+}
+
+/// 在质量范围内二分搜索不超过目标的最高质量
+fn search_quality<F>(
+    image: &DynamicImage,
+    target_bytes: usize,
+    min_quality: u8,
+    max_quality: u8,
+    encode: &F,
+) -> Result<QualitySearch>
+where
+    F: Fn(&DynamicImage, u8) -> Result<Vec<u8>>,
+{
+    // 最高质量已满足时无需搜索
+    let best = encode(image, max_quality)?;
+    if best.len() <= target_bytes {
+        return Ok(QualitySearch::Found { data: best, quality: max_quality });
+    }
+    MEMORY_POOL.return_buffer(best);
+
+    let lowest = encode(image, min_quality)?;
+    if lowest.len() > target_bytes {
+        let smallest_size = lowest.len();
+        MEMORY_POOL.return_buffer(lowest);
+        return Ok(QualitySearch::TooLarge { smallest_size });
+    }
+
+    // 不变式: lo 满足目标, hi 超出目标
+    let (mut lo, mut hi) = (min_quality, max_quality);
+    let mut found = (lowest, min_quality);
+    for _ in 0..MAX_QUALITY_STEPS {
+        if hi - lo <= 1 {
+            break;
+        }
+        let mid = lo + (hi - lo) / 2;
+        let data = encode(image, mid)?;
+        if data.len() <= target_bytes {
+            lo = mid;
+            let previous = std::mem::replace(&mut found, (data, mid));
+            MEMORY_POOL.return_buffer(previous.0);
+        } else {
+            hi = mid;
+            MEMORY_POOL.return_buffer(data);
+        }
+    }
+
+    let (data, quality) = found;
+    Ok(QualitySearch::Found { data, quality })
+}
+
+/// 以固定质量编码RGB图像
+fn encode_jpeg_rgb(rgb_image: &image::RgbImage, quality: u8, encoder: &JpegEncoderSettings) -> Result<Vec<u8>> {
+    match encoder.backend {
+        JpegBackend::Standard => encode_jpeg_rgb_standard(rgb_image, quality),
+        JpegBackend::Mozjpeg => encode_jpeg_rgb_mozjpeg(rgb_image, quality, encoder),
+    }
+}
+
+/// 使用 image 库内置编码器
+fn encode_jpeg_rgb_standard(rgb_image: &image::RgbImage, quality: u8) -> Result<Vec<u8>> {
     let width = rgb_image.width();
     let height = rgb_image.height();
-    let raw_data = rgb_image.as_raw();
 
     // 使用内存池获取输出缓冲区
     let estimated_size = (width * height * 3 / 4) as usize; // 预估压缩后大小
@@ -68,23 +345,52 @@ pub fn turbo_encode_jpeg(
     let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output_buffer, quality);
 
     // 执行编码
-    encoder.encode(raw_data, width, height, image::ExtendedColorType::Rgb8)
-        .context("JPEG编码失败")?;
+    encoder.encode(rgb_image.as_raw(), width, height, image::ExtendedColorType::Rgb8)
+        .map_err(|e| ConvertError::encode("JPEG", e))?;
+
+    Ok(output_buffer)
+}
 
-    // 检查大小限制
-    if let Some(max_size) = target_size_bytes {
-        if output_buffer.len() > max_size && quality > 10 {
-            // 递归降质量重试（最多3次）
-            MEMORY_POOL.return_buffer(output_buffer);
-            let new_quality = (quality as f32 * 0.8) as u8;
-            return turbo_encode_jpeg(image, new_quality, target_size_bytes);
+/// 使用 mozjpeg 编码器
+fn encode_jpeg_rgb_mozjpeg(rgb_image: &image::RgbImage, quality: u8, encoder: &JpegEncoderSettings) -> Result<Vec<u8>> {
+    let width = rgb_image.width() as usize;
+    let height = rgb_image.height() as usize;
+    let estimated_size = width * height * 3 / 4;
+    let settings = *encoder;
+
+    // mozjpeg 出错时会 panic 并穿过 C 代码展开，必须在这里捕获
+    let result = std::panic::catch_unwind(|| -> std::io::Result<Vec<u8>> {
+        let mut comp = mozjpeg::Compress::new(mozjpeg::ColorSpace::JCS_RGB);
+
+        // 默认配置即 mozjpeg 的最大压缩档（含网格量化）；
+        // 关闭网格量化时退回 libjpeg-turbo 的快速档。jpeg_set_defaults 会重置参数，必须最先调用
+        if !settings.trellis {
+            comp.set_fastest_defaults();
         }
-    }
 
-    log::debug!("JPEG编码耗时: {:?}, 质量: {}, 大小: {} bytes",
-               start.elapsed(), quality, output_buffer.len());
+        comp.set_size(width, height);
+        comp.set_quality(quality as f32);
 
-    Ok(output_buffer)
+        let chroma = settings.chroma_subsampling.pixel_sizes();
+        comp.set_chroma_sampling_pixel_sizes(chroma, chroma);
+
+        if settings.progressive {
+            comp.set_progressive_mode();
+        } else {
+            // 清除扫描脚本即为基线（顺序）编码
+            comp.set_optimize_scans(false);
+        }
+        comp.set_optimize_coding(settings.optimize_huffman);
+
+        let mut started = comp.start_compress(MEMORY_POOL.get_buffer(estimated_size))?;
+        started.write_scanlines(rgb_image.as_raw())?;
+        started.finish()
+    });
+
+    match result {
+        Ok(data) => data.map_err(|e| ConvertError::encode("JPEG", e)),
+        Err(_) => Err(ConvertError::encode("JPEG", "mozjpeg编码器内部错误")),
+    }
 }
 
 /// 超高性能PNG编码器 - 无压缩模式
@@ -109,7 +415,7 @@ pub fn turbo_encode_png_fast(image: &DynamicImage) -> Result<Vec<u8>> {
         image.width(),
         image.height(),
         image.color().into(),
-    ).context("PNG快速编码失败")?;
+    ).map_err(|e| ConvertError::encode("PNG", e))?;
 
     // 截取实际使用的部分
     let actual_size = cursor.position() as usize;
@@ -121,58 +427,101 @@ pub fn turbo_encode_png_fast(image: &DynamicImage) -> Result<Vec<u8>> {
     Ok(buffer)
 }
 
-/// 超高性能PNG编码器 - 压缩模式（智能压缩）
+/// 超高性能PNG编码器 - 压缩模式（类似 pngquant 的有损压缩）
+///
+/// 依次尝试：无损编码 → 自适应调色板（逐级减少颜色） → 缩小尺寸。
+/// 只有在最少颜色仍超出目标且允许缩放时才会缩小图片，尽量保留原始分辨率。
 pub fn turbo_encode_png_compressed(
     image: &DynamicImage,
-    target_bytes: usize
+    target_bytes: usize,
+    quant: &PngQuantOptions,
+    search: &TargetSearch,
 ) -> Result<Vec<u8>> {
     let start = std::time::Instant::now();
 
-    // 快速估算需要的缩放比例
-    let quick_size = estimate_png_size(image.width(), image.height());
-
-    if quick_size <= target_bytes {
-        // 不需要压缩，直接编码
-        return turbo_encode_png_fast(image);
+    // 无损编码已满足时直接返回
+    let lossless = encode_png_lossless(image)?;
+    if lossless.len() <= target_bytes {
+        log::debug!("PNG无损编码耗时: {:?}, 大小: {} bytes", start.elapsed(), lossless.len());
+        return Ok(lossless);
     }
 
-    // 计算所需的缩放比例
-    let scale_factor = ((target_bytes as f32 / quick_size as f32).sqrt() * 0.95).min(1.0);
-
-    if scale_factor < 0.1 {
-        return Err(anyhow::anyhow!("目标大小过小，无法压缩到指定大小"));
+    let mut smallest_size = lossless.len();
+    let steps = if quant.enabled { quant.color_steps() } else { Vec::new() };
+    for &colors in &steps {
+        let data = encode_png_quantized(image, colors, quant.dithering)?;
+        if data.len() <= target_bytes {
+            log::debug!("PNG调色板编码耗时: {:?}, 颜色数: {}, 大小: {} bytes",
+                       start.elapsed(), colors, data.len());
+            return Ok(data);
+        }
+        smallest_size = smallest_size.min(data.len());
     }
 
-    // 执行智能缩放
-    let new_width = (image.width() as f32 * scale_factor).round().max(32.0) as u32;
-    let new_height = (image.height() as f32 * scale_factor).round().max(32.0) as u32;
-
-    // 使用智能滤镜缩放
-    let filter = if scale_factor > 0.8 {
-        image::imageops::FilterType::Lanczos3
-    } else if scale_factor > 0.5 {
-        image::imageops::FilterType::CatmullRom
-    } else {
-        image::imageops::FilterType::Triangle
-    };
-
-    let resized = image.resize_exact(new_width, new_height, filter);
-    let result = turbo_encode_png_fast(&resized)?;
+    if !search.allow_downscale {
+        return Err(ConvertError::TargetTooSmall { format: "PNG", target_kb: target_bytes / 1024, scale: 1.0 });
+    }
 
-    log::debug!("PNG压缩编码耗时: {:?}, 缩放: {:.2}x, 大小: {} bytes",
-               start.elapsed(), scale_factor, result.len());
+    // 最后手段：缩小尺寸，缩放后仍使用最少颜色的调色板（若启用）
+    let fallback_colors = steps.last().copied();
+    let mut scale = 1.0f32;
+    for _ in 0..MAX_DOWNSCALE_STEPS {
+        let ratio = (target_bytes as f32 / smallest_size as f32).sqrt() * 0.95;
+        let next_scale = scale * ratio.min(0.95);
+        if next_scale < search.min_scale {
+            break;
+        }
+        scale = next_scale;
+
+        let new_width = ((image.width() as f32 * scale).round() as u32).max(1);
+        let new_height = ((image.height() as f32 * scale).round() as u32).max(1);
+
+        // 使用智能滤镜缩放
+        let filter = if scale > 0.8 {
+            image::imageops::FilterType::Lanczos3
+        } else if scale > 0.5 {
+            image::imageops::FilterType::CatmullRom
+        } else {
+            image::imageops::FilterType::Triangle
+        };
+        let resized = image.resize_exact(new_width, new_height, filter);
+
+        let data = match fallback_colors {
+            Some(colors) => encode_png_quantized(&resized, colors, quant.dithering)?,
+            None => encode_png_lossless(&resized)?,
+        };
+        if data.len() <= target_bytes {
+            log::debug!("PNG压缩编码耗时: {:?}, 缩放: {:.2}x, 大小: {} bytes",
+                       start.elapsed(), scale, data.len());
+            return Ok(data);
+        }
+        smallest_size = data.len();
+    }
 
-    Ok(result)
+    Err(ConvertError::TargetTooSmall { format: "PNG", target_kb: target_bytes / 1024, scale })
 }
 
-/// 智能估算PNG文件大小
-fn estimate_png_size(width: u32, height: u32) -> usize {
-    // 基于经验公式估算PNG大小
-    let pixels = width * height;
-    let base_size = pixels as usize * 3; // RGB数据
-    let compression_ratio = 0.7; // 假设70%压缩率
+/// 无损PNG编码（最高压缩 + 自适应滤波）
+fn encode_png_lossless(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    let encoder = image::codecs::png::PngEncoder::new_with_quality(
+        &mut buffer,
+        image::codecs::png::CompressionType::Best,
+        image::codecs::png::FilterType::Adaptive,
+    );
+    encoder.write_image(
+        image.as_bytes(),
+        image.width(),
+        image.height(),
+        image.color().into(),
+    ).map_err(|e| ConvertError::encode("PNG", e))?;
+    Ok(buffer)
+}
 
-    (base_size as f32 * compression_ratio) as usize + 1024 // 加上header开销
+/// 调色板量化后编码
+fn encode_png_quantized(image: &DynamicImage, colors: u16, dithering: bool) -> Result<Vec<u8>> {
+    let quantized = png_quantizer::quantize(image, colors, dithering);
+    png_quantizer::encode_indexed_png(&quantized)
 }
 
 /// 批量并行编码 - 利用多核心
@@ -211,6 +560,73 @@ mod tests {
         assert!(data.len() > 100); // 应该产生一些数据
     }
 
+    /// 生成带噪声的测试图，使编码大小随质量明显变化
+    fn noisy_image(width: u32, height: u32) -> DynamicImage {
+        let mut seed = 12345u32;
+        let img = image::RgbImage::from_fn(width, height, |x, y| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            let noise = (seed >> 24) as u8;
+            image::Rgb([(x % 256) as u8 ^ noise, (y % 256) as u8, noise])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn test_jpeg_target_search_stays_under_target() {
+        let test_image = noisy_image(400, 300);
+        let target = 40 * 1024;
+        let result = turbo_encode_jpeg_to_target(&test_image, target, &TargetSearch::default(), &JpegEncoderSettings::default()).unwrap();
+
+        assert!(result.data.len() <= target);
+        assert!(result.quality >= 10 && result.quality <= 95);
+        assert_eq!(result.scale, 1.0);
+
+        // 质量再高一级就会超出目标（或已达到上限）
+        if result.quality < 95 {
+            let rgb = test_image.to_rgb8();
+            let next = encode_jpeg_rgb(&rgb, result.quality + 1, &JpegEncoderSettings::default()).unwrap();
+            assert!(next.len() > target);
+        }
+    }
+
+    #[test]
+    fn test_jpeg_target_search_downscales_when_needed() {
+        let test_image = noisy_image(800, 600);
+        let target = 8 * 1024;
+        let result = turbo_encode_jpeg_to_target(&test_image, target, &TargetSearch::default(), &JpegEncoderSettings::default()).unwrap();
+        assert!(result.data.len() <= target);
+        assert!(result.scale < 1.0);
+
+        let no_downscale = TargetSearch { allow_downscale: false, ..Default::default() };
+        assert!(turbo_encode_jpeg_to_target(&test_image, target, &no_downscale, &JpegEncoderSettings::default()).is_err());
+    }
+
+    #[test]
+    fn test_mozjpeg_backend_options() {
+        let test_image = noisy_image(320, 240);
+        let target = 30 * 1024;
+        for &(progressive, trellis) in &[(true, true), (false, false)] {
+            for (subsampling, _) in ChromaSubsampling::all_modes() {
+                let encoder = JpegEncoderSettings {
+                    backend: JpegBackend::Mozjpeg,
+                    progressive,
+                    trellis,
+                    optimize_huffman: true,
+                    chroma_subsampling: subsampling,
+                };
+                let result = turbo_encode_jpeg_to_target(&test_image, target, &TargetSearch::default(), &encoder).unwrap();
+                assert!(result.data.len() <= target);
+
+                let decoded = image::load_from_memory_with_format(&result.data, image::ImageFormat::Jpeg).unwrap();
+                assert_eq!((decoded.width(), decoded.height()), (320, 240));
+            }
+        }
+
+        // 配置文件只写部分选项时其余使用默认值
+        let encoder: JpegEncoderSettings = serde_json::from_str(r#"{"backend":"Mozjpeg","trellis":false}"#).unwrap();
+        assert_eq!(encoder, JpegEncoderSettings { backend: JpegBackend::Mozjpeg, trellis: false, ..Default::default() });
+    }
+
     #[test]
     fn test_turbo_png_fast() {
         let test_image = DynamicImage::new_rgb8(100, 100);
@@ -219,9 +635,16 @@ mod tests {
     }
 
     #[test]
-    fn test_size_estimation() {
-        let size = estimate_png_size(1000, 1000);
-        assert!(size > 1000000); // 应该是一个合理的大小
-        assert!(size < 5000000); // 但不会过大
+    fn test_png_compressed_prefers_palette_over_resize() {
+        let test_image = noisy_image(300, 200);
+        let lossless = encode_png_lossless(&test_image).unwrap();
+        let target = lossless.len() / 2;
+
+        let data = turbo_encode_png_compressed(&test_image, target, &PngQuantOptions::default(), &TargetSearch::default()).unwrap();
+        assert!(data.len() <= target);
+
+        // 调色板量化已足够，不应缩小尺寸
+        let decoded = image::load_from_memory(&data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (300, 200));
     }
 }
\ No newline at end of file
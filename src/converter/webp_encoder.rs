@@ -1,6 +1,6 @@
 // WebP现代图像格式编码器 - 支持有损和无损压缩
 
-use anyhow::Result;
+use crate::converter::error::Result;
 use image::DynamicImage;
 // std::io::Cursor 已移除 - 未在代码中使用
 
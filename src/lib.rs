@@ -1,6 +1,7 @@
 // 库文件 - 公开内部模块供测试使用
 
 pub mod app;
+pub mod cli;
 pub mod converter;
 pub mod utils;
 pub mod ui;
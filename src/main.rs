@@ -1,5 +1,6 @@
 // 声明所有模块
 mod app;
+mod cli;
 mod converter;
 mod ui;
 mod utils;
@@ -14,6 +15,11 @@ use ui::styles;
 fn main() -> Result<(), eframe::Error> {
     env_logger::init();
 
+    // 以子命令启动时进入命令行模式，不创建窗口
+    if cli::is_cli_invocation() {
+        std::process::exit(cli::run(std::env::args_os()));
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0])
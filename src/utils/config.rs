@@ -2,6 +2,16 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use crate::converter::simple_watermark::{WatermarkPosition, SimpleTextWatermark, ImageWatermark};
+use crate::converter::image_converter::EncodeOptions;
+use crate::converter::metadata::MetadataPolicy;
+use crate::converter::discovery::DiscoverySettings;
+use crate::converter::naming::NamingSettings;
+use crate::converter::output_guard::OutputSettings;
+use crate::converter::pdf_converter::PdfRenderSettings;
+use crate::converter::pdf_text::PdfTextSettings;
+use crate::converter::png_quantizer::PngQuantOptions;
+use crate::converter::resize::ResizeSettings;
+use crate::converter::turbo_encoder::{JpegEncoderSettings, TargetSearch};
 use image::Rgba;
 
 /// 应用程序配置
@@ -29,6 +39,33 @@ pub struct AppConfig {
     pub watermark_settings: WatermarkSettings,
     /// PDF转换设置
     pub pdf_settings: PdfSettings,
+    /// 元数据保留策略（EXIF、ICC、版权信息）
+    #[serde(default)]
+    pub metadata_policy: MetadataPolicy,
+    /// 编码前的尺寸调整
+    #[serde(default)]
+    pub resize_settings: ResizeSettings,
+    /// 输出文件命名模板
+    #[serde(default)]
+    pub naming: NamingSettings,
+    /// 文件夹模式的遍历和输出目录结构
+    #[serde(default)]
+    pub folder_settings: FolderSettings,
+    /// 输出文件冲突策略和替换原文件模式
+    #[serde(default)]
+    pub output_settings: OutputSettings,
+    /// 文件夹模式的文件过滤规则
+    #[serde(default)]
+    pub discovery: DiscoverySettings,
+    /// 图片转换时按输出目录中的任务日志继续上次的任务
+    #[serde(default)]
+    pub resume_batch: bool,
+    /// PDF页面渲染设置（页面范围等），PDF转图片和图片转换中的PDF输入共用
+    #[serde(default)]
+    pub pdf_render: PdfRenderSettings,
+    /// PDF文本提取设置，PDF转图片和图片转换中的PDF输入共用
+    #[serde(default)]
+    pub pdf_text: PdfTextSettings,
 }
 
 /// 压缩模式配置
@@ -52,6 +89,8 @@ pub enum OutputFormat {
     WebPLossy,
     /// WebP格式（无损压缩，比PNG更小）
     WebPLossless,
+    /// AVIF格式（有损压缩，体积最小）
+    Avif,
 }
 
 /// 处理模式配置
@@ -90,15 +129,30 @@ pub struct WindowSettings {
 /// 高级设置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdvancedSettings {
-    /// JPEG质量范围
+    /// JPEG质量范围（目标大小搜索的上下限）
     pub jpeg_quality_range: (u8, u8),
+    /// 最低质量仍超出目标大小时，是否允许缩小图片尺寸
+    #[serde(default = "default_allow_downscale")]
+    pub allow_downscale: bool,
+    /// 允许缩小到的最小比例
+    #[serde(default = "default_min_downscale_scale")]
+    pub min_downscale_scale: f32,
+    /// JPEG编码器（后端、渐进式、网格量化、霍夫曼优化、色度子采样）
+    #[serde(default)]
+    pub jpeg_encoder: JpegEncoderSettings,
     /// PNG压缩级别
     pub png_compression_level: u8,
+    /// PNG调色板量化（压缩PNG超出目标时先减少颜色，再缩小尺寸）
+    #[serde(default)]
+    pub png_quantization: PngQuantOptions,
     /// PDF渲染DPI
     pub pdf_render_dpi: f32,
-    /// 最大并发处理数量
+    /// 批处理同时处理的最大文件数，0 表示使用全部CPU核心
     pub max_concurrent_jobs: usize,
-    /// 是否保留原始文件
+    /// 批处理同时解码图片占用的内存上限（MB），0 表示自动（当前可用内存的一半）
+    #[serde(default)]
+    pub memory_limit_mb: usize,
+    /// 替换原文件模式下是否先备份原文件
     pub keep_original_files: bool,
     /// 是否显示详细进度
     pub show_detailed_progress: bool,
@@ -154,6 +208,18 @@ pub struct PdfSettings {
     pub default_output_name: String,
 }
 
+/// 文件夹模式设置
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FolderSettings {
+    /// 是否处理子文件夹中的文件
+    pub recursive: bool,
+    /// 最大遍历深度，1 表示只处理输入文件夹本身的文件，0 表示不限制
+    pub max_depth: usize,
+    /// 在输出文件夹中重建输入文件夹的子目录结构，避免不同子目录的同名文件互相覆盖
+    pub preserve_structure: bool,
+}
+
 /// PDF页面方向
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum PdfPageOrientation {
@@ -165,6 +231,14 @@ pub enum PdfPageOrientation {
     Portrait,
 }
 
+fn default_allow_downscale() -> bool {
+    true
+}
+
+fn default_min_downscale_scale() -> f32 {
+    0.1
+}
+
 /// 获取应用程序数据目录
 fn get_app_data_dir() -> PathBuf {
     let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
@@ -194,6 +268,15 @@ impl Default for AppConfig {
             advanced_settings: AdvancedSettings::default(),
             watermark_settings: WatermarkSettings::default(),
             pdf_settings: PdfSettings::default(),
+            metadata_policy: MetadataPolicy::default(),
+            resize_settings: ResizeSettings::default(),
+            naming: NamingSettings::default(),
+            folder_settings: FolderSettings::default(),
+            output_settings: OutputSettings::default(),
+            discovery: DiscoverySettings::default(),
+            resume_batch: false,
+            pdf_render: PdfRenderSettings::default(),
+            pdf_text: PdfTextSettings::default(),
         }
     }
 }
@@ -219,9 +302,14 @@ impl Default for AdvancedSettings {
     fn default() -> Self {
         Self {
             jpeg_quality_range: (10, 95),
+            allow_downscale: default_allow_downscale(),
+            min_downscale_scale: default_min_downscale_scale(),
+            jpeg_encoder: JpegEncoderSettings::default(),
             png_compression_level: 6,
+            png_quantization: PngQuantOptions::default(),
             pdf_render_dpi: 150.0,
             max_concurrent_jobs: 4,
+            memory_limit_mb: 0,
             keep_original_files: true,
             show_detailed_progress: true,
             auto_open_output_folder: false,
@@ -326,6 +414,16 @@ impl AppConfig {
         self.save_to_file(config_path)
     }
 
+    /// 转换为编码参数
+    pub fn encode_options(&self) -> EncodeOptions {
+        EncodeOptions {
+            target_search: self.advanced_settings.to_target_search(),
+            jpeg_encoder: self.advanced_settings.jpeg_encoder,
+            png_quantization: self.advanced_settings.png_quantization,
+            metadata_policy: self.metadata_policy,
+        }
+    }
+
     // 已移除 reset_to_default 方法 - 未使用
     // 已移除 validate 方法 - 未使用
 }
@@ -357,6 +455,19 @@ impl WatermarkSettings {
     }
 }
 
+impl AdvancedSettings {
+    /// 转换为JPEG目标大小搜索参数
+    pub fn to_target_search(&self) -> TargetSearch {
+        let (low, high) = self.jpeg_quality_range;
+        TargetSearch {
+            min_quality: low.min(high),
+            max_quality: low.max(high),
+            allow_downscale: self.allow_downscale,
+            min_scale: self.min_downscale_scale.clamp(0.01, 1.0),
+        }
+    }
+}
+
 impl OutputFormat {
     /// 获取文件扩展名
     pub fn extension(&self) -> &'static str {
@@ -364,6 +475,7 @@ impl OutputFormat {
             OutputFormat::Jpeg => "jpg",
             OutputFormat::PngCompressed | OutputFormat::PngOriginal => "png",
             OutputFormat::WebPLossy | OutputFormat::WebPLossless => "webp",
+            OutputFormat::Avif => "avif",
         }
     }
 
@@ -377,6 +489,7 @@ impl OutputFormat {
             (OutputFormat::PngOriginal, "PNG (原始)"),
             (OutputFormat::WebPLossy, "WebP (有损)"),
             (OutputFormat::WebPLossless, "WebP (无损)"),
+            (OutputFormat::Avif, "AVIF"),
         ]
     }
 
@@ -414,6 +527,38 @@ impl Default for PdfSettings {
     }
 }
 
+impl Default for FolderSettings {
+    fn default() -> Self {
+        Self {
+            recursive: true,
+            max_depth: 0,
+            preserve_structure: true,
+        }
+    }
+}
+
+impl FolderSettings {
+    /// 遍历的最大深度（文件相对输入文件夹的层级）
+    pub fn walk_depth(&self) -> usize {
+        match (self.recursive, self.max_depth) {
+            (false, _) => 1,
+            (true, 0) => usize::MAX,
+            (true, depth) => depth,
+        }
+    }
+
+    /// `file` 的输出文件夹：保留结构时为输出文件夹下与输入文件夹相同的相对子目录
+    pub fn output_dir_for(&self, input_root: &Path, file: &Path, output_root: &Path) -> PathBuf {
+        if !self.preserve_structure {
+            return output_root.to_path_buf();
+        }
+        match file.parent().and_then(|parent| parent.strip_prefix(input_root).ok()) {
+            Some(relative) => output_root.join(relative),
+            None => output_root.to_path_buf(),
+        }
+    }
+}
+
 impl AppMode {
     // 已移除 display_name 方法 - 未使用
 
@@ -1,21 +1,7 @@
 use anyhow::Result; // --- 【清理】移除了未使用的 `Context` ---
-use std::path::{Path, PathBuf};
+use std::path::Path;
 // 已移除 sysinfo::Disks 导入 - 未使用
-use walkdir::WalkDir;
-
-/// 获取指定目录下的所有文件路径
-pub fn get_files_in_directory(dir: &Path) -> Result<Vec<PathBuf>> {
-    if !dir.is_dir() {
-        anyhow::bail!("提供的路径不是一个目录: {}", dir.display());
-    }
-    let files = WalkDir::new(dir)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| e.file_type().is_file())
-        .map(|e| e.into_path())
-        .collect();
-    Ok(files)
-}
+// 输入文件遍历已移到 converter::discovery，按文件内容识别格式
 
 /// 在文件浏览器中打开指定文件夹
 pub fn open_folder_in_explorer(path: &Path) -> Result<()> {
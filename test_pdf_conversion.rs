@@ -21,10 +21,11 @@ fn main() {
             margin_mm: 0.0,
             auto_rotate: true,
             page_mode: image_converter::converter::image_to_pdf::PageMode::AdaptiveSize,
+            discovery: Default::default(),
         };
 
         println!("🔄 测试1: 保持宽高比模式...");
-        match image_converter::converter::image_to_pdf::ImageToPdfConverter::convert_single_image(&test_image, &pdf_config_aspect) {
+        match image_converter::converter::image_to_pdf::ImageToPdfConverter::convert_single_image(&test_image, &pdf_config_aspect, &Default::default()) {
             Ok(()) => {
                 println!("✅ 保持宽高比PDF转换成功！输出文件: test_aspect_ratio_output.pdf");
             },
@@ -44,10 +45,11 @@ fn main() {
             margin_mm: 0.0,
             auto_rotate: true,
             page_mode: image_converter::converter::image_to_pdf::PageMode::AdaptiveSize,
+            discovery: Default::default(),
         };
 
         println!("🔄 测试2: 拉伸填满模式...");
-        match image_converter::converter::image_to_pdf::ImageToPdfConverter::convert_single_image(&test_image, &pdf_config_stretch) {
+        match image_converter::converter::image_to_pdf::ImageToPdfConverter::convert_single_image(&test_image, &pdf_config_stretch, &Default::default()) {
             Ok(()) => {
                 println!("✅ 拉伸填满PDF转换成功！输出文件: test_stretch_output.pdf");
             },
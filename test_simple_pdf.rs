@@ -29,10 +29,11 @@ fn main() {
             margin_mm: 0.0,
             auto_rotate: true,
             page_mode: PageMode::AdaptiveSize,
+            discovery: Default::default(),
         };
 
         println!("🔄 转换图片到PDF...");
-        match ImageToPdfConverter::convert_single_image(&image_path, &config) {
+        match ImageToPdfConverter::convert_single_image(&image_path, &config, &image_converter::converter::cancel::CancellationToken::new()) {
             Ok(()) => {
                 println!("✅ PDF转换成功！输出文件: test_simple_output.pdf");
 